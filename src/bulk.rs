@@ -0,0 +1,155 @@
+use crate::error::{Error, Result};
+use rusqlite::Connection;
+use strum_macros::{Display, EnumString};
+
+// only the first failures are kept so a bad file can't fill up memory
+const MAX_FAILURES: usize = 100;
+
+#[derive(Display, EnumString, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum BulkMode {
+    // the first failed record rolls back every record before it
+    #[strum(ascii_case_insensitive)]
+    Atomic,
+    // failed records are rolled back on their own and collected in the summary
+    #[strum(ascii_case_insensitive)]
+    Continue,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BulkOptions {
+    pub mode: BulkMode,
+    // records per transaction in continue mode, atomic loads use a single one
+    pub batch_size: usize,
+}
+
+impl Default for BulkOptions {
+    fn default() -> Self {
+        Self {
+            mode: BulkMode::Atomic,
+            batch_size: 1000,
+        }
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct BulkSummary {
+    pub inserted: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    // (record number, reason) for the first failed records
+    pub failures: Vec<(usize, String)>,
+}
+
+impl BulkSummary {
+    pub fn records(&self) -> usize {
+        self.inserted + self.skipped + self.failed
+    }
+
+    fn fail(&mut self, record: usize, reason: String) {
+        self.failed += 1;
+
+        if self.failures.len() < MAX_FAILURES {
+            self.failures.push((record, reason));
+        }
+    }
+}
+
+// runs the writes of a bulk load inside transactions, anything left
+// uncommitted when it is dropped is rolled back
+pub(crate) struct Batch<'a> {
+    conn: &'a Connection,
+    options: BulkOptions,
+    pending: usize,
+    open: bool,
+    summary: BulkSummary,
+}
+
+impl<'a> Batch<'a> {
+    pub fn begin(conn: &'a Connection, options: BulkOptions) -> Result<Self> {
+        conn.execute_batch("BEGIN")?;
+
+        Ok(Self {
+            conn,
+            options,
+            pending: 0,
+            open: true,
+            summary: BulkSummary::default(),
+        })
+    }
+
+    // `write` returns false when the record was skipped
+    pub fn record<F: FnOnce() -> Result<bool>>(&mut self, write: F) -> Result<()> {
+        let record = self.summary.records() + 1;
+
+        match self.options.mode {
+            BulkMode::Atomic => match write() {
+                Ok(true) => self.summary.inserted += 1,
+                Ok(false) => self.summary.skipped += 1,
+                Err(err) => {
+                    self.rollback()?;
+
+                    return Err(Error::Record {
+                        record,
+                        source: Box::new(err),
+                    });
+                }
+            },
+            BulkMode::Continue => {
+                self.conn.execute_batch("SAVEPOINT `record`")?;
+
+                match write() {
+                    Ok(inserted) => {
+                        self.conn.execute_batch("RELEASE `record`")?;
+
+                        match inserted {
+                            true => self.summary.inserted += 1,
+                            false => self.summary.skipped += 1,
+                        }
+                    }
+                    Err(err) => {
+                        self.conn
+                            .execute_batch("ROLLBACK TO `record`; RELEASE `record`")?;
+                        self.summary.fail(record, err.to_string());
+                    }
+                }
+
+                self.pending += 1;
+
+                if self.pending >= self.options.batch_size.max(1) {
+                    self.conn.execute_batch("COMMIT; BEGIN")?;
+                    self.pending = 0;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // records a failure that happened before there was a record to write
+    pub fn fail(&mut self, err: Error) -> Result<()> {
+        self.record(|| Err(err))
+    }
+
+    pub fn commit(mut self) -> Result<BulkSummary> {
+        self.conn.execute_batch("COMMIT")?;
+        self.open = false;
+
+        Ok(std::mem::take(&mut self.summary))
+    }
+
+    fn rollback(&mut self) -> Result<()> {
+        if self.open {
+            self.conn.execute_batch("ROLLBACK")?;
+            self.open = false;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Drop for Batch<'a> {
+    fn drop(&mut self) {
+        let _ = self.rollback();
+    }
+}
@@ -0,0 +1,178 @@
+use crate::error::Result;
+use rusqlite::{Connection, OptionalExtension};
+use std::str::FromStr;
+use strum_macros::{Display, EnumString};
+
+// index names can't contain `_`, so this can't clash with an index
+const TABLE: &str = "index_catalog";
+
+pub(crate) const NOW: &str = "strftime('%Y-%m-%dT%H:%M:%SZ', 'now')";
+
+// what happens when a document's key field doesn't match the key it's saved under
+#[derive(Display, EnumString, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[strum(serialize_all = "lowercase")]
+pub enum KeyCheck {
+    #[strum(ascii_case_insensitive)]
+    Off,
+    #[default]
+    #[strum(ascii_case_insensitive)]
+    Warn,
+    #[strum(ascii_case_insensitive)]
+    Reject,
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexInfo {
+    pub name: String,
+    // the document field keys are taken from by init and import
+    pub key_field: Option<String>,
+    pub key_check: KeyCheck,
+    pub documents: usize,
+    // bytes used by the index and the tables that belong to it
+    pub size: u64,
+    // indexes made before the catalog existed have no created time
+    pub created: Option<String>,
+    pub updated: Option<String>,
+}
+
+pub(crate) struct Entry {
+    pub key_field: Option<String>,
+    pub key_check: KeyCheck,
+    pub created: Option<String>,
+    pub updated: Option<String>,
+}
+
+pub fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE if not exists `{table}` (
+            `name` TEXT PRIMARY KEY,
+            `key_field` TEXT,
+            `key_check` TEXT,
+            `created` TEXT,
+            `updated` TEXT);
+        ",
+        table = TABLE
+    ))?;
+
+    // catalogs made before key checks existed
+    let has_key_check: bool = conn.query_row(
+        "SELECT count(*) FROM pragma_table_info(?1) WHERE `name` = 'key_check'",
+        [TABLE],
+        |row| row.get(0),
+    )?;
+
+    if !has_key_check {
+        conn.execute_batch(&format!(
+            "ALTER TABLE `{table}` ADD COLUMN `key_check` TEXT",
+            table = TABLE
+        ))?;
+    }
+
+    Ok(())
+}
+
+// a recreated index starts again, apart from its key field
+pub fn register(conn: &Connection, index: &str) -> Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT INTO `{table}` (`name`, `created`, `updated`) VALUES (?1, {now}, {now})
+            ON CONFLICT (`name`) DO UPDATE SET `created` = {now}, `updated` = {now}",
+            table = TABLE,
+            now = NOW
+        ),
+        [index],
+    )?;
+
+    Ok(())
+}
+
+pub fn remove(conn: &Connection, index: &str) -> Result<()> {
+    conn.execute(
+        &format!("DELETE FROM `{table}` WHERE `name` = ?1", table = TABLE),
+        [index],
+    )?;
+
+    Ok(())
+}
+
+// marks the index as changed, adding it to the catalog if it's missing
+pub fn touch(conn: &Connection, index: &str) -> Result<()> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "INSERT INTO `{table}` (`name`, `updated`) VALUES (?1, {now})
+        ON CONFLICT (`name`) DO UPDATE SET `updated` = {now}",
+        table = TABLE,
+        now = NOW
+    ))?;
+
+    stmt.execute([index])?;
+
+    Ok(())
+}
+
+pub fn set_key_field(conn: &Connection, index: &str, key_field: &str) -> Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT INTO `{table}` (`name`, `key_field`, `updated`) VALUES (?1, ?2, {now})
+            ON CONFLICT (`name`) DO UPDATE SET `key_field` = ?2, `updated` = {now}",
+            table = TABLE,
+            now = NOW
+        ),
+        [index, key_field],
+    )?;
+
+    Ok(())
+}
+
+pub fn set_key_check(conn: &Connection, index: &str, key_check: KeyCheck) -> Result<()> {
+    conn.execute(
+        &format!(
+            "INSERT INTO `{table}` (`name`, `key_check`, `updated`) VALUES (?1, ?2, {now})
+            ON CONFLICT (`name`) DO UPDATE SET `key_check` = ?2, `updated` = {now}",
+            table = TABLE,
+            now = NOW
+        ),
+        [index, &key_check.to_string()],
+    )?;
+
+    Ok(())
+}
+
+pub(crate) fn entry(conn: &Connection, index: &str) -> Result<Option<Entry>> {
+    let entry = conn
+        .query_row(
+            &format!(
+                "SELECT `key_field`, `key_check`, `created`, `updated` FROM `{table}`
+                WHERE `name` = ?1",
+                table = TABLE
+            ),
+            [index],
+            |row| {
+                Ok(Entry {
+                    key_field: row.get(0)?,
+                    // unset or unknown checks fall back to the default
+                    key_check: row
+                        .get::<_, Option<String>>(1)?
+                        .and_then(|check| KeyCheck::from_str(&check).ok())
+                        .unwrap_or_default(),
+                    created: row.get(2)?,
+                    updated: row.get(3)?,
+                })
+            },
+        )
+        .optional()?;
+
+    Ok(entry)
+}
+
+// the pages of the index table, its primary key and every `{index}_` table
+pub fn size(conn: &Connection, index: &str) -> Result<u64> {
+    let size: Option<i64> = conn.query_row(
+        "SELECT SUM(`pgsize`) FROM `dbstat`
+        WHERE `name` = ?1 OR `name` LIKE ?1 || '\\_%' ESCAPE '\\'
+            OR `name` = 'sqlite_autoindex_' || ?1 || '_1'",
+        [index],
+        |row| row.get(0),
+    )?;
+
+    Ok(size.unwrap_or(0) as u64)
+}
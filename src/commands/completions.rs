@@ -0,0 +1,330 @@
+use crate::tools::config::{Output, Settings};
+use crate::tools::gui::GUI;
+use crate::tools::validation::StringValidation;
+use crate::traits::command::{derive_getters, named_arg, ActionSpec, Name, ParamRule, Params};
+use crate::traits::command::{Command, Runnable};
+use crate::Commands;
+use anyhow::{Ok, Result};
+use rusty_search::Engine;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumString};
+
+const GLOBAL_FLAGS: [&str; 2] = ["--db", "--output"];
+
+pub struct Completions {
+    pub params: Params,
+}
+
+#[derive(Display, EnumString, Debug)]
+enum Actions {
+    #[strum(ascii_case_insensitive)]
+    Bash,
+    #[strum(ascii_case_insensitive)]
+    Zsh,
+    #[strum(ascii_case_insensitive)]
+    Fish,
+    // called by the scripts with the words typed so far, not listed in help
+    #[strum(ascii_case_insensitive)]
+    Complete,
+    #[strum(ascii_case_insensitive)]
+    Help,
+}
+
+impl Command for Completions {
+    derive_getters!();
+
+    fn actions(&self) -> Vec<ActionSpec> {
+        vec![
+            ActionSpec {
+                name: "bash",
+                rules: vec![],
+                description: "Print the completion script for bash",
+            },
+            ActionSpec {
+                name: "zsh",
+                rules: vec![],
+                description: "Print the completion script for zsh",
+            },
+            ActionSpec {
+                name: "fish",
+                rules: vec![],
+                description: "Print the completion script for fish",
+            },
+        ]
+    }
+
+    // the scripts are printed on their own so they can be piped into a file
+    fn print_title(&self, action: &str, _params: &[String]) -> bool {
+        !matches!(
+            Actions::from_str(action),
+            Result::Ok(Actions::Bash | Actions::Zsh | Actions::Fish | Actions::Complete)
+        )
+    }
+
+    fn help(&self) -> Result<()> {
+        GUI::text()
+            .title("Completions Command")
+            .sub_title("actions:")
+            .nl()
+            .actions(&self.actions())
+            .nl()
+            .content("* bash: add `source <(rusty_search completions bash)` to ~/.bashrc")
+            .content("* zsh:  add `source <(rusty_search completions zsh)` to ~/.zshrc after compinit")
+            .content("* fish: rusty_search completions fish > ~/.config/fish/completions/rusty_search.fish")
+            .content("* index names are read from the database when completing, `--db` is followed")
+            .nl();
+
+        Ok(())
+    }
+}
+
+impl Runnable for Completions {
+    fn run(&mut self, action: &str, params: &[String]) -> Result<()> {
+        let action = Actions::from_str(action).unwrap_or(Actions::Help);
+
+        match action {
+            Actions::Bash => print!("{}", BASH),
+            Actions::Zsh => print!("{}", ZSH),
+            Actions::Fish => print!("{}", FISH),
+            Actions::Complete => Completions::complete(params),
+            Actions::Help => self.help()?,
+        }
+
+        Ok(())
+    }
+}
+
+impl Completions {
+    pub fn new() -> Self {
+        Self {
+            params: HashMap::default(),
+        }
+    }
+
+    // prints the candidates for the last word, one per line. nothing is
+    // printed when there are none so the shell can fall back to file names
+    fn complete(params: &[String]) {
+        let words = match params.split_first() {
+            Some((first, rest)) if first == "--" => rest,
+            _ => params,
+        };
+        let (current, words) = match words.split_last() {
+            Some((current, words)) => (current.as_str(), words),
+            None => ("", words),
+        };
+
+        for candidate in Completions::candidates(words, current) {
+            if candidate.starts_with(current) {
+                println!("{}", candidate);
+            }
+        }
+    }
+
+    fn candidates(words: &[String], current: &str) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut db = None;
+        let mut words = words.iter();
+
+        // global flags can be anywhere before `--`, like in main
+        while let Some(word) = words.next() {
+            match word.as_str() {
+                "--" => {
+                    args.push(word.as_str());
+                    args.extend(words.by_ref().map(|word| word.as_str()));
+                }
+                "--db" => match words.next() {
+                    Some(path) => db = Some(path.as_str()),
+                    None => return vec![],
+                },
+                "--output" => match words.next() {
+                    Some(_) => {}
+                    None => return Output::iter().map(|output| output.to_string()).collect(),
+                },
+                flag if flag.starts_with("--db=") => db = flag.strip_prefix("--db="),
+                flag if flag.starts_with("--output=") => {}
+                word => args.push(word),
+            }
+        }
+
+        let command = match args.first() {
+            Some(command) => command,
+            None if current.starts_with('-') => {
+                return GLOBAL_FLAGS.iter().map(|flag| flag.to_string()).collect()
+            }
+            None => {
+                return Commands::iter()
+                    .map(|command| command.to_string().to_lowercase())
+                    .collect()
+            }
+        };
+
+        let route = match Commands::from_str(command) {
+            Result::Ok(command) => command.route(),
+            Err(_) => return vec![],
+        };
+
+        let action = match args.get(1) {
+            Some(action) => action,
+            None => {
+                let mut actions: Vec<String> = route
+                    .actions()
+                    .iter()
+                    .map(|action| action.name.to_string())
+                    .collect();
+                actions.push(String::from("help"));
+
+                return actions;
+            }
+        };
+
+        let rules = match route.action(action) {
+            Some(action) => action.rules,
+            None => return vec![],
+        };
+
+        // work out which param the current word is for, the same way
+        // `assert_params` hands out values
+        let mut named = HashSet::new();
+        let mut pending: Option<&ParamRule> = None;
+        let mut positional = 0;
+        let mut ended = false;
+
+        for arg in args.iter().skip(2) {
+            if pending.take().is_some() {
+                continue;
+            }
+            if ended {
+                positional += 1;
+                continue;
+            }
+            if *arg == "--" {
+                ended = true;
+                continue;
+            }
+
+            match named_arg(arg) {
+                Some((name, value)) => {
+                    let rule = rules.iter().find(|rule| match name {
+                        Name::Long(key) => rule.key == key,
+                        Name::Short(short) => rule.short == Some(short),
+                    });
+
+                    if let Some(rule) = rule {
+                        named.insert(rule.key);
+
+                        if value.is_none() && !rule.flag {
+                            pending = Some(rule);
+                        }
+                    }
+                }
+                None => positional += 1,
+            }
+        }
+
+        if let Some(rule) = pending {
+            return Completions::values(rule, db);
+        }
+
+        let unnamed: Vec<&ParamRule> = rules
+            .iter()
+            .filter(|rule| !named.contains(rule.key))
+            .collect();
+
+        // only params that haven't been given yet are offered by name
+        if current.starts_with('-') && !ended {
+            let mut flags: Vec<String> = unnamed
+                .iter()
+                .skip(positional)
+                .map(|rule| format!("--{}", rule.key))
+                .collect();
+            flags.push(String::from("--help"));
+
+            return flags;
+        }
+
+        // a repeated param keeps taking values when only optional params follow it
+        let rest = unnamed
+            .iter()
+            .enumerate()
+            .find(|(i, rule)| rule.repeated && unnamed[i + 1..].iter().all(|rule| !*rule.required));
+
+        let rule = match (rest, unnamed.get(positional)) {
+            (Some((i, rule)), _) if positional >= i => rule,
+            (_, Some(rule)) => rule,
+            _ => return vec![],
+        };
+
+        Completions::values(rule, db)
+    }
+
+    fn values(rule: &ParamRule, db: Option<&str>) -> Vec<String> {
+        if !rule.choices.is_empty() {
+            return rule
+                .choices
+                .iter()
+                .map(|choice| choice.to_string())
+                .collect();
+        }
+
+        match rule.validation {
+            StringValidation::SqlTable => Completions::index_names(db),
+            StringValidation::Bool => vec![String::from("true"), String::from("false")],
+            _ => vec![],
+        }
+    }
+
+    // an error here would only garble the shell, so it just means no names.
+    // a missing database isn't opened as that would create it
+    fn index_names(db: Option<&str>) -> Vec<String> {
+        let db = db.unwrap_or(&Settings::get().db);
+
+        if !Path::new(db).exists() {
+            return vec![];
+        }
+
+        Engine::open(db)
+            .and_then(|engine| engine.index_names())
+            .unwrap_or_default()
+    }
+}
+
+const BASH: &str = r#"_rusty_search() {
+    local IFS=$'\n'
+    COMPREPLY=($(rusty_search completions complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null))
+}
+
+complete -o default -F _rusty_search rusty_search
+"#;
+
+const ZSH: &str = r#"#compdef rusty_search
+
+_rusty_search() {
+    local -a candidates
+    candidates=("${(@f)$(rusty_search completions complete -- "${(@)words[2,CURRENT]}" 2>/dev/null)}")
+
+    if [[ -n "${candidates[1]}" ]]; then
+        compadd -a candidates
+    else
+        _files
+    fi
+}
+
+compdef _rusty_search rusty_search
+"#;
+
+const FISH: &str = r#"function __rusty_search_complete
+    set -l words (commandline -opc)
+    set -l candidates (rusty_search completions complete -- $words[2..-1] (commandline -ct) 2>/dev/null)
+
+    if set -q candidates[1]
+        printf '%s\n' $candidates
+    else
+        __fish_complete_path (commandline -ct)
+    end
+end
+
+complete -c rusty_search -f -a '(__rusty_search_complete)'
+"#;
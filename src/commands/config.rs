@@ -0,0 +1,277 @@
+use crate::tools::config::{ConfigFile, Setting};
+use crate::tools::gui::GUI;
+use crate::tools::report::Report;
+use crate::tools::validation::StringValidation::Ignore;
+use crate::traits::command::{derive_getters, ActionSpec, ParamRule, Params};
+use crate::traits::command::{Command, Runnable};
+use anyhow::{bail, Ok, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::env;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumString};
+
+pub struct Config {
+    pub params: Params,
+}
+
+#[derive(Display, EnumString, Debug)]
+enum Actions {
+    #[strum(ascii_case_insensitive)]
+    Get,
+    #[strum(ascii_case_insensitive)]
+    Set,
+    #[strum(ascii_case_insensitive)]
+    Unset,
+    #[strum(ascii_case_insensitive)]
+    List,
+    #[strum(ascii_case_insensitive)]
+    Help,
+}
+
+impl Command for Config {
+    derive_getters!();
+
+    fn actions(&self) -> Vec<ActionSpec> {
+        vec![
+            ActionSpec {
+                name: "get",
+                rules: Config::rules(&Actions::Get),
+                description: "Show the value of {setting} and where it was set",
+            },
+            ActionSpec {
+                name: "set",
+                rules: Config::rules(&Actions::Set),
+                description: "Save {value} for {setting} in the config file",
+            },
+            ActionSpec {
+                name: "unset",
+                rules: Config::rules(&Actions::Unset),
+                description: "Remove {setting} from the config file",
+            },
+            ActionSpec {
+                name: "list",
+                rules: Config::rules(&Actions::List),
+                description: "Show every setting and the config file location",
+            },
+        ]
+    }
+
+    fn help(&self) -> Result<()> {
+        GUI::text()
+            .title("Config Command")
+            .sub_title("actions:")
+            .nl()
+            .actions(&self.actions())
+            .nl()
+            .sub_title("settings:")
+            .nl()
+            .content("db:     path of the database file, default `db.db`")
+            .content("output: format results are printed in, text|json|ndjson, default `text`")
+            .content("address: address the `serve` command listens on, default `127.0.0.1:7700`")
+            .nl()
+            .content(
+                "* settings can be overridden with RUSTY_SEARCH_{SETTING} environment variables",
+            )
+            .content(
+                "* the db can be overridden for a single run with the global `--db {path}` flag",
+            )
+            .content("* the config file location can be changed with RUSTY_SEARCH_CONFIG")
+            .nl();
+
+        Ok(())
+    }
+}
+
+impl Runnable for Config {
+    fn run(&mut self, action: &str, params: &[String]) -> Result<()> {
+        let action = Actions::from_str(action).unwrap_or(Actions::Help);
+
+        match action {
+            Actions::Get => self.get(params)?,
+            Actions::Set => self.set(params)?,
+            Actions::Unset => self.unset(params)?,
+            Actions::List => self.list()?,
+            Actions::Help => self.help()?,
+        }
+
+        Ok(())
+    }
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Self {
+            params: HashMap::default(),
+        }
+    }
+
+    fn rules(action: &Actions) -> Vec<ParamRule<'static>> {
+        match action {
+            Actions::Get => vec![ParamRule {
+                key: "setting",
+                validation: Ignore,
+                required: &true,
+                choices: &["db", "output", "address"],
+                ..Default::default()
+            }],
+            Actions::Set => vec![
+                ParamRule {
+                    key: "setting",
+                    validation: Ignore,
+                    required: &true,
+                    choices: &["db", "output", "address"],
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "value",
+                    validation: Ignore,
+                    required: &true,
+                    ..Default::default()
+                },
+            ],
+            Actions::Unset => vec![ParamRule {
+                key: "setting",
+                validation: Ignore,
+                required: &true,
+                choices: &["db", "output", "address"],
+                ..Default::default()
+            }],
+            _ => vec![],
+        }
+    }
+
+    fn get(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Config::rules(&Actions::Get), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let setting = Config::setting(self.get_param("setting"))?;
+        let file = ConfigFile::load()?;
+
+        GUI::new()
+            .report(&Report::data(Config::resolved(&file, setting)))
+            .sub_title("result:")
+            .content(&Config::describe(&file, setting))
+            .nl();
+
+        Ok(())
+    }
+
+    fn set(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Config::rules(&Actions::Set), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let setting = Config::setting(self.get_param("setting"))?;
+        let mut file = ConfigFile::load()?;
+
+        file.set(setting, self.get_param("value"))?;
+        file.save()?;
+
+        GUI::new().report(
+            &Report::success(&format!(
+                "{setting} set to '{value}' in {path}",
+                setting = setting,
+                value = self.get_param("value"),
+                path = file.path.display()
+            ))
+            .with_data(Config::resolved(&file, setting)),
+        );
+
+        Ok(())
+    }
+
+    fn unset(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Config::rules(&Actions::Unset), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let setting = Config::setting(self.get_param("setting"))?;
+        let mut file = ConfigFile::load()?;
+
+        let report = match file.unset(setting) {
+            true => {
+                file.save()?;
+                Report::success(&format!(
+                    "{setting} unset, now using the default '{default}'",
+                    setting = setting,
+                    default = setting.default_value()
+                ))
+            }
+            false => Report::warning(&format!("{} was not set in the config file", setting)),
+        };
+
+        GUI::new().report(&report.with_data(Config::resolved(&file, setting)));
+
+        Ok(())
+    }
+
+    fn list(&self) -> Result<()> {
+        let file = ConfigFile::load()?;
+
+        GUI::new().report(&Report::data(json!({
+            "path": file.path.display().to_string(),
+            "settings": Setting::iter()
+                .map(|setting| Config::resolved(&file, setting))
+                .collect::<Vec<Value>>(),
+        })));
+
+        GUI::new()
+            .sub_title("config file:")
+            .content(&file.path.display().to_string())
+            .nl()
+            .sub_title("settings:");
+
+        for setting in Setting::iter() {
+            GUI::new().content(&Config::describe(&file, setting));
+        }
+
+        GUI::new().nl();
+
+        Ok(())
+    }
+}
+
+impl Config {
+    fn setting(name: &str) -> Result<Setting> {
+        match Setting::from_str(name) {
+            Result::Ok(setting) => Ok(setting),
+            Err(_) => bail!(format!(
+                "Unknown setting '{}', expected {}",
+                name,
+                Setting::iter()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<String>>()
+                    .join("|")
+            )),
+        }
+    }
+
+    // the value of a setting outside of any flags and where it came from
+    fn source(file: &ConfigFile, setting: Setting) -> (String, String) {
+        match (env::var(setting.env_var()), file.get(setting)) {
+            (Result::Ok(value), _) => (value, setting.env_var()),
+            (_, Some(value)) => (value.to_string(), "config file".to_string()),
+            _ => (setting.default_value().to_string(), "default".to_string()),
+        }
+    }
+
+    fn resolved(file: &ConfigFile, setting: Setting) -> Value {
+        let (value, source) = Config::source(file, setting);
+
+        json!({ "setting": setting.to_string(), "value": value, "source": source })
+    }
+
+    fn describe(file: &ConfigFile, setting: Setting) -> String {
+        let (value, source) = Config::source(file, setting);
+
+        format!(
+            "{setting}: {value} ({source})",
+            setting = setting,
+            value = value,
+            source = source
+        )
+    }
+}
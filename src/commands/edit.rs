@@ -1,27 +1,40 @@
+use crate::tools::config::Settings;
 use crate::tools::gui::GUI;
-use crate::tools::validation::StringValidation::{Bool, Ignore, SqlColumn, SqlTable};
-use crate::traits::command::{derive_getters, ParamRule};
+use crate::tools::report::{Report, Reported};
+use crate::tools::validation::StringValidation::{
+    Bool, Count, Fields, Filter, Ignore, Json, JsonObject, SqlTable,
+};
+use crate::traits::command::{derive_getters, ActionSpec, ParamRule, Params};
 use crate::traits::command::{Command, Runnable};
 use anyhow::{Ok, Result};
-use rusqlite::Connection;
+use rusty_search::{Filter as SearchFilter, Hit, Index, PatchFormat, SearchOptions};
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::str::FromStr;
 use strum_macros::{Display, EnumString};
 
-const DB: &str = "db.db";
-
 pub struct Edit {
-    pub params: HashMap<String, String>,
+    pub params: Params,
 }
 
 #[derive(Display, EnumString, Debug)]
 enum Actions {
+    #[strum(ascii_case_insensitive)]
+    Get,
+    #[strum(ascii_case_insensitive)]
+    Mget,
     #[strum(ascii_case_insensitive)]
     Add,
     #[strum(ascii_case_insensitive)]
     Update,
     #[strum(ascii_case_insensitive)]
+    Patch,
+    #[strum(ascii_case_insensitive)]
     Remove,
+    #[strum(serialize = "remove-by-query", ascii_case_insensitive)]
+    RemoveByQuery,
+    #[strum(serialize = "update-by-query", ascii_case_insensitive)]
+    UpdateByQuery,
     #[strum(ascii_case_insensitive)]
     Help,
 }
@@ -31,16 +44,72 @@ impl Edit {}
 impl Command for Edit {
     derive_getters!();
 
+    fn actions(&self) -> Vec<ActionSpec> {
+        vec![
+            ActionSpec {
+                name: "get",
+                rules: Edit::rules(&Actions::Get),
+                description: "Show the entry in {index} that matches {key}",
+            },
+            ActionSpec {
+                name: "mget",
+                rules: Edit::rules(&Actions::Mget),
+                description: "Show the entries in {index} that match each of {keys}",
+            },
+            ActionSpec {
+                name: "add",
+                rules: Edit::rules(&Actions::Add),
+                description: "Create a new entry in {index} with {key} and {data}",
+            },
+            ActionSpec {
+                name: "update",
+                rules: Edit::rules(&Actions::Update),
+                description: "Update entry in {index} that matches {key} with {data}",
+            },
+            ActionSpec {
+                name: "patch",
+                rules: Edit::rules(&Actions::Patch),
+                description:
+                    "Change fields of the entry in {index} that matches {key} with {patch}",
+            },
+            ActionSpec {
+                name: "remove",
+                rules: Edit::rules(&Actions::Remove),
+                description: "Delete the entry in {index} that matches {key}",
+            },
+            ActionSpec {
+                name: "remove-by-query",
+                rules: Edit::rules(&Actions::RemoveByQuery),
+                description: "Delete every entry in {index} found by {query}",
+            },
+            ActionSpec {
+                name: "update-by-query",
+                rules: Edit::rules(&Actions::UpdateByQuery),
+                description: "Merge {patch} into every entry in {index} found by {query}",
+            },
+        ]
+    }
+
     fn help(&self) -> Result<()> {
-        GUI::new()
+        GUI::text()
             .title("Edit Command")
             .sub_title("actions:")
             .nl()
-            .content("create: {index} {key} {data}              | Create a new entry in {index} with {key} and {data}")
-            .content("update: {index} {key} {data} {?create}    | Update entry in {index} that matches {key} with {data}")
-            .content("remove: {index} {key}                     | Delete index and data")
+            .actions(&self.actions())
             .nl()
-            .content("* {?create} is an optional bool to create a new entry on no key match")
+            .content("* {?--fields|-f} limits get and mget to fields like `name,brand.name`, {?--compact|-c}")
+            .content("  prints each entry on one line")
+            .content("* {?--create|-c} adds a new entry when nothing matches {key}")
+            .content("* a {key} of `-` takes the key from the index's key field in {data}")
+            .content("* when {data} has a different value in the key field the index warns, rejects or allows it")
+            .content("  as set by `manage key`")
+            .content("* {?--if-version|-v} only changes the entry while it's at that version, so concurrent")
+            .content("  edits fail instead of overwriting each other. they exit with 3 and a `conflict` status")
+            .content("* {patch} is a merge patch like {\"price\": 5, \"old\": null} or a json patch")
+            .content("  like [{\"op\": \"replace\", \"path\": \"/price\", \"value\": 5}], {?--format|-f} picks one")
+            .content("* remove-by-query and update-by-query only count the matching entries until")
+            .content("  {?--apply|-a} is given, then change all of them or none")
+            .content("* {?--filter|-f} narrows them down, or picks them without a {query}, see `search help`")
             .nl();
 
         Ok(())
@@ -48,13 +117,18 @@ impl Command for Edit {
 }
 
 impl Runnable for Edit {
-    fn run(&mut self, action: &String, params: &[String]) -> Result<()> {
+    fn run(&mut self, action: &str, params: &[String]) -> Result<()> {
         let action = Actions::from_str(action).unwrap_or(Actions::Help);
 
         match action {
+            Actions::Get => self.get(params)?,
+            Actions::Mget => self.mget(params)?,
             Actions::Add => self.add(params)?,
             Actions::Update => self.update(params)?,
+            Actions::Patch => self.patch(params)?,
             Actions::Remove => self.remove(params)?,
+            Actions::RemoveByQuery => self.remove_by_query(params)?,
+            Actions::UpdateByQuery => self.update_by_query(params)?,
             Actions::Help => self.help()?,
         }
 
@@ -63,200 +137,649 @@ impl Runnable for Edit {
 }
 
 impl Edit {
+    // `-` takes the key from the key field of the document
+    fn key(index: &Index, key: &str, data: &Value) -> Result<String> {
+        Ok(match key {
+            "-" => index.derive_key(data)?,
+            key => key.to_string(),
+        })
+    }
+
+    // a key that doesn't match the document still saves, but as a warning
+    fn success(message: &str, warning: Option<String>) -> Report {
+        match warning {
+            Some(warning) => Report::warning(&format!("{}, but {}", message, warning)),
+            None => Report::success(message),
+        }
+    }
+
+    // the entry as pretty printed or compact json, lines for the text output
+    fn print_entry(hit: &Hit, compact: bool) {
+        let gui = GUI::new();
+        gui.content(&format!(
+            "{key} (v{version}):",
+            key = hit.key,
+            version = hit.version
+        ));
+
+        let json = match compact {
+            true => hit.document.to_string(),
+            false => serde_json::to_string_pretty(&hit.document).unwrap_or_default(),
+        };
+
+        for line in json.lines() {
+            gui.content(&format!("  {}", line));
+        }
+    }
+
+    fn search_options(&self) -> Result<SearchOptions> {
+        Ok(SearchOptions {
+            filter: match self.get_param_opt("filter") {
+                Some(filter) => Some(SearchFilter::parse(filter)?),
+                None => None,
+            },
+            ..Default::default()
+        })
+    }
+
+    fn if_version(&self) -> Result<Option<u64>> {
+        Ok(match self.get_param_opt("if-version") {
+            Some(version) => Some(version.parse()?),
+            None => None,
+        })
+    }
+
     pub fn new() -> Self {
         Self {
             params: HashMap::default(),
         }
     }
 
-    fn add(&mut self, params: &[String]) -> Result<()> {
-        self.assert_params(
-            vec![
+    fn rules(action: &Actions) -> Vec<ParamRule<'static>> {
+        match action {
+            Actions::Get => vec![
                 ParamRule {
                     key: "index",
                     validation: SqlTable,
                     required: &true,
+                    ..Default::default()
                 },
                 ParamRule {
                     key: "key",
-                    validation: SqlColumn,
+                    validation: Ignore,
                     required: &true,
+                    ..Default::default()
                 },
                 ParamRule {
-                    key: "data",
+                    key: "fields",
+                    validation: Fields,
+                    required: &false,
+                    short: Some('f'),
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "compact",
+                    validation: Bool,
+                    required: &false,
+                    short: Some('c'),
+                    flag: true,
+                    ..Default::default()
+                },
+            ],
+            Actions::Mget => vec![
+                ParamRule {
+                    key: "index",
+                    validation: SqlTable,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "keys",
                     validation: Ignore,
                     required: &true,
+                    repeated: true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "fields",
+                    validation: Fields,
+                    required: &false,
+                    short: Some('f'),
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "compact",
+                    validation: Bool,
+                    required: &false,
+                    short: Some('c'),
+                    flag: true,
+                    ..Default::default()
                 },
             ],
-            params,
-        )?;
-
-        GUI::new().print_params(self as &dyn Command);
-
-        let conn = Connection::open(DB)?;
-        let result = self.add_new_entry(&conn)?;
-
-        GUI::new().sub_title("result:").content(&result).nl();
-
-        Ok(())
-    }
-
-    fn update(&mut self, params: &[String]) -> Result<()> {
-        self.assert_params(
-            vec![
+            Actions::Add => vec![
                 ParamRule {
                     key: "index",
                     validation: SqlTable,
                     required: &true,
+                    ..Default::default()
                 },
                 ParamRule {
                     key: "key",
-                    validation: SqlColumn,
+                    validation: Ignore,
                     required: &true,
+                    ..Default::default()
                 },
                 ParamRule {
                     key: "data",
+                    validation: Json,
+                    required: &true,
+                    ..Default::default()
+                },
+            ],
+            Actions::Update => vec![
+                ParamRule {
+                    key: "index",
+                    validation: SqlTable,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "key",
                     validation: Ignore,
                     required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "data",
+                    validation: Json,
+                    required: &true,
+                    ..Default::default()
                 },
                 ParamRule {
                     key: "create",
                     validation: Bool,
                     required: &false,
+                    short: Some('c'),
+                    flag: true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "if-version",
+                    validation: Count,
+                    required: &false,
+                    short: Some('v'),
+                    ..Default::default()
+                },
+            ],
+            Actions::Patch => vec![
+                ParamRule {
+                    key: "index",
+                    validation: SqlTable,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "key",
+                    validation: Ignore,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "patch",
+                    validation: Json,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "format",
+                    validation: Ignore,
+                    required: &false,
+                    short: Some('f'),
+                    choices: &["merge", "json"],
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "if-version",
+                    validation: Count,
+                    required: &false,
+                    short: Some('v'),
+                    ..Default::default()
+                },
+            ],
+            Actions::Remove => vec![
+                ParamRule {
+                    key: "index",
+                    validation: SqlTable,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "key",
+                    validation: Ignore,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "if-version",
+                    validation: Count,
+                    required: &false,
+                    short: Some('v'),
+                    ..Default::default()
+                },
+            ],
+            Actions::RemoveByQuery => vec![
+                ParamRule {
+                    key: "index",
+                    validation: SqlTable,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "query",
+                    validation: Ignore,
+                    required: &false,
+                    repeated: true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "filter",
+                    validation: Filter,
+                    required: &false,
+                    short: Some('f'),
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "apply",
+                    validation: Bool,
+                    required: &false,
+                    short: Some('a'),
+                    flag: true,
+                    ..Default::default()
                 },
             ],
-            params,
-        )?;
+            Actions::UpdateByQuery => vec![
+                ParamRule {
+                    key: "index",
+                    validation: SqlTable,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "patch",
+                    validation: JsonObject,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "query",
+                    validation: Ignore,
+                    required: &false,
+                    repeated: true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "filter",
+                    validation: Filter,
+                    required: &false,
+                    short: Some('f'),
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "apply",
+                    validation: Bool,
+                    required: &false,
+                    short: Some('a'),
+                    flag: true,
+                    ..Default::default()
+                },
+            ],
+            _ => vec![],
+        }
+    }
+
+    fn get(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Edit::rules(&Actions::Get), params)?;
 
         GUI::new().print_params(self as &dyn Command);
 
-        let mut action = String::with_capacity(21);
+        let engine = Settings::engine()?;
+        let index = engine.index(self.get_param("index"))?;
+        let key = self.get_param("key");
+
+        let mut hit = match index.get_document(key)? {
+            Some(hit) => hit,
+            None => {
+                GUI::new().report(
+                    &Report::error(&format!(
+                        "No matching entry for {index}->{key}",
+                        index = self.get_param("index"),
+                        key = key
+                    ))
+                    .index(self.get_param("index"))
+                    .key(key)
+                    .count(0),
+                );
+
+                return Err(Reported.into());
+            }
+        };
 
-        let conn = Connection::open(DB)?;
-        let create = self.get_param_bool("create");
+        if let Some(fields) = self.get_param_opt("fields") {
+            hit.project(&fields.split(',').collect::<Vec<&str>>());
+        }
 
-        if create && !self.does_entry_exist(&conn)? {
-            self.add_new_entry(&conn)?;
+        GUI::new().report(
+            &Report::success(&format!(
+                "Found entry for {index}->{key}",
+                index = self.get_param("index"),
+                key = key
+            ))
+            .index(self.get_param("index"))
+            .key(key)
+            .count(1)
+            .with_data(hit.to_json()),
+        );
 
-            action.push_str("Added new")
-        } else {
-            self.update_entry(&conn)?;
+        GUI::new().sub_title("entry:");
+        Edit::print_entry(&hit, self.get_param_bool("compact"));
+        GUI::new().nl();
+
+        Ok(())
+    }
+
+    // keys without an entry are listed as missing instead of failing the rest
+    fn mget(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Edit::rules(&Actions::Mget), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let engine = Settings::engine()?;
+        let index = engine.index(self.get_param("index"))?;
+        let keys = self.get_param_values("keys");
+        let fields: Option<Vec<&str>> = self
+            .get_param_opt("fields")
+            .map(|fields| fields.split(',').collect());
+
+        let mut hits = Vec::new();
+        let mut entries = Vec::new();
+        let mut missing = Vec::new();
+
+        for (key, hit) in keys.iter().zip(index.get_documents(keys)?) {
+            match hit {
+                Some(mut hit) => {
+                    if let Some(fields) = &fields {
+                        hit.project(fields);
+                    }
+
+                    entries.push(hit.to_json());
+                    hits.push(hit);
+                }
+                None => {
+                    entries.push(json!({ "key": key, "data": null }));
+                    missing.push(key.as_str());
+                }
+            }
+        }
+
+        let report = match missing.is_empty() {
+            true => Report::success(&format!("Found {} entries", hits.len())),
+            false => Report::warning(&format!(
+                "Found {} of {} entries, no match for {}",
+                hits.len(),
+                keys.len(),
+                missing.join(", ")
+            )),
+        };
+
+        GUI::new().report(
+            &report
+                .index(self.get_param("index"))
+                .count(hits.len())
+                .with_data(Value::Array(entries)),
+        );
+
+        if !hits.is_empty() {
+            GUI::new().sub_title("entries:");
 
-            match self.update_entry(&conn)? {
-                true => action.push_str("Updated entry"),
-                false => action.push_str("No matching entry for"),
+            for hit in hits.iter() {
+                Edit::print_entry(hit, self.get_param_bool("compact"));
             }
+
+            GUI::new().nl();
         }
 
-        GUI::new()
-            .sub_title("result:")
-            .content(&format!(
-                "Success: {result} for {index}->{key}",
-                result = action,
+        Ok(())
+    }
+
+    fn add(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Edit::rules(&Actions::Add), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let engine = Settings::engine()?;
+        let index = engine.index(self.get_param("index"))?;
+        let data = serde_json::from_str(self.get_param("data"))?;
+        let key = Edit::key(&index, self.get_param("key"), &data)?;
+        let warning = index.check_key(&key, &data)?;
+
+        index.add_document(&key, &data)?;
+
+        GUI::new().report(
+            &Edit::success(
+                &format!(
+                    "Entry added for {index}->{key}",
+                    index = self.get_param("index"),
+                    key = key,
+                ),
+                warning,
+            )
+            .count(1)
+            .index(self.get_param("index"))
+            .key(&key),
+        );
+
+        Ok(())
+    }
+
+    fn update(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Edit::rules(&Actions::Update), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let engine = Settings::engine()?;
+        let index = engine.index(self.get_param("index"))?;
+        let create = self.get_param_bool("create");
+        let data = serde_json::from_str(self.get_param("data"))?;
+        let key = &Edit::key(&index, self.get_param("key"), &data)?;
+        let warning = index.check_key(key, &data)?;
+
+        let action = if create && !index.has_document(key)? {
+            index.add_document(key, &data)?;
+
+            Some(("Added new", 1))
+        } else {
+            index
+                .update_document(key, &data, self.if_version()?)?
+                .map(|version| ("Updated entry", version))
+        };
+
+        let report = match action {
+            Some((action, version)) => Edit::success(
+                &format!(
+                    "{result} for {index}->{key} at version {version}",
+                    result = action,
+                    index = self.get_param("index"),
+                    key = key,
+                    version = version
+                ),
+                warning,
+            )
+            .with_data(json!({ "version": version }))
+            .count(1),
+            None => Report::warning(&format!(
+                "No matching entry for {index}->{key}",
                 index = self.get_param("index"),
-                key = self.get_param("key")
+                key = key
             ))
-            .nl();
+            .count(0),
+        };
+
+        GUI::new().report(&report.index(self.get_param("index")).key(key));
+
+        Ok(())
+    }
+
+    fn patch(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Edit::rules(&Actions::Patch), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let engine = Settings::engine()?;
+        let index = engine.index(self.get_param("index"))?;
+        let key = self.get_param("key");
+        let patch = serde_json::from_str(self.get_param("patch"))?;
+        let format = match self.get_param_opt("format") {
+            Some(format) => PatchFormat::from_str(format)?,
+            None => PatchFormat::detect(&patch),
+        };
+
+        let report = match index.patch_document(key, &patch, format, self.if_version()?)? {
+            Some((hit, warning)) => Edit::success(
+                &format!(
+                    "Patched entry for {index}->{key} at version {version}",
+                    index = self.get_param("index"),
+                    key = key,
+                    version = hit.version
+                ),
+                warning,
+            )
+            .with_data(hit.to_json())
+            .count(1),
+            None => Report::warning(&format!(
+                "No matching entry for {index}->{key}",
+                index = self.get_param("index"),
+                key = key
+            ))
+            .count(0),
+        };
+
+        GUI::new().report(&report.index(self.get_param("index")).key(key));
 
         Ok(())
     }
 
     fn remove(&mut self, params: &[String]) -> Result<()> {
-        self.assert_params(
-            vec![
-                ParamRule {
-                    key: "index",
-                    validation: SqlTable,
-                    required: &true,
-                },
-                ParamRule {
-                    key: "key",
-                    validation: Ignore,
-                    required: &true,
-                },
-            ],
-            params,
-        )?;
+        self.assert_params(Edit::rules(&Actions::Remove), params)?;
 
         GUI::new().print_params(self as &dyn Command);
 
-        let conn = Connection::open(DB)?;
+        let engine = Settings::engine()?;
+        let index = engine.index(self.get_param("index"))?;
 
-        let row_existed = self.remove_entry(&conn)?;
+        let row_existed = index.remove_document(self.get_param("key"), self.if_version()?)?;
 
-        let result = match row_existed {
-            true => format!(
-                "Success: Row removed with key '{key}'",
+        let report = match row_existed {
+            true => Report::success(&format!(
+                "Row removed with key '{key}'",
                 key = self.get_param("key")
-            ),
-            false => format!(
-                "Warning: Row did not exist with key '{key}'",
+            )),
+            false => Report::warning(&format!(
+                "Row did not exist with key '{key}'",
                 key = self.get_param("key")
-            ),
+            )),
         };
 
-        GUI::new().sub_title("result:").content(&result).nl();
-        // TODO:
-        //      add gui feedback for this commands actions
-        //      ensure help command reflects this new command correctly
-        //      remove SqlColumn from validation as the key param is the column value, not column name
-
+        GUI::new().report(
+            &report
+                .index(self.get_param("index"))
+                .key(self.get_param("key"))
+                .count(usize::from(row_existed)),
+        );
         Ok(())
     }
-}
 
-impl Edit {
-    fn does_entry_exist(&self, conn: &Connection) -> Result<bool> {
-        let result: bool = conn.query_row(
-            &format!(
-                "SELECT count(*) FROM `{table}` WHERE `key` = ?1",
-                table = self.get_param("index"),
-            ),
-            [self.get_param("key")],
-            |row| row.get(0),
-        )?;
-
-        Ok(result)
-    }
+    fn remove_by_query(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Edit::rules(&Actions::RemoveByQuery), params)?;
 
-    fn add_new_entry(&self, conn: &Connection) -> Result<String> {
-        match conn.execute(
-            &format!(
-                "INSERT INTO {table} (`key`, `data`) VALUES (?1, ?2)",
-                table = self.get_param("index")
-            ),
-            [self.get_param("key"), self.get_param("data")],
-        ) {
-            Err(err) => Ok("Error: ".to_string() + &err.to_string()),
-            _ => Ok(format!(
-                "Success: Entry added for {index}->{key}",
-                index = self.get_param("index"),
-                key = self.get_param("key"),
-            )),
+        GUI::new().print_params(self as &dyn Command);
+
+        let engine = Settings::engine()?;
+        let index = engine.index(self.get_param("index"))?;
+        let query = self.get_param_values("query").join(" ");
+        let options = self.search_options()?;
+
+        if !self.get_param_bool("apply") {
+            return self.dry_run(&index, &query, &options, "removed");
         }
+
+        let removed = index.remove_by_query(&query, &options)?;
+
+        GUI::new().report(
+            &Report::success(&format!("Removed {} entries", removed))
+                .index(self.get_param("index"))
+                .count(removed),
+        );
+
+        Ok(())
     }
 
-    fn remove_entry(&self, conn: &Connection) -> Result<bool> {
-        let rows_effected = conn.execute(
-            &format!(
-                "DELETE FROM {table} WHERE `key` = ?1",
-                table = self.get_param("index")
-            ),
-            [self.get_param("key")],
-        )?;
+    fn update_by_query(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Edit::rules(&Actions::UpdateByQuery), params)?;
 
-        Ok(rows_effected > 0)
+        GUI::new().print_params(self as &dyn Command);
+
+        let engine = Settings::engine()?;
+        let index = engine.index(self.get_param("index"))?;
+        let query = self.get_param_values("query").join(" ");
+        let patch = serde_json::from_str(self.get_param("patch"))?;
+        let options = self.search_options()?;
+
+        if !self.get_param_bool("apply") {
+            return self.dry_run(&index, &query, &options, "updated");
+        }
+
+        let updated = index.update_by_query(&query, &options, &patch)?;
+
+        GUI::new().report(
+            &Report::success(&format!("Updated {} entries", updated))
+                .index(self.get_param("index"))
+                .count(updated),
+        );
+
+        Ok(())
     }
 
-    fn update_entry(&self, conn: &Connection) -> Result<bool> {
-        let updated_users = conn.execute(
-            &format!(
-                "UPDATE {table} SET `data` = ?1 WHERE `key` = ?2",
-                table = self.get_param("index")
-            ),
-            [self.get_param("data"), self.get_param("key")],
-        )?;
+    // lists what a by-query action would change without changing it
+    fn dry_run(
+        &self,
+        index: &Index,
+        query: &str,
+        options: &SearchOptions,
+        change: &str,
+    ) -> Result<()> {
+        let keys = index.search_keys(query, options)?;
+
+        GUI::new().report(
+            &Report::warning(&format!(
+                "{count} entries would be {change}, add --apply to change them",
+                count = keys.len(),
+                change = change
+            ))
+            .index(index.name())
+            .count(keys.len())
+            .with_data(json!({ "dry_run": true, "keys": keys })),
+        );
+
+        if !keys.is_empty() {
+            GUI::new().sub_title("entries:");
+
+            for key in keys.iter() {
+                GUI::new().content(key);
+            }
 
-        Ok(updated_users > 0)
+            GUI::new().nl();
+        }
+
+        Ok(())
     }
 }
@@ -1,5 +1,5 @@
 use crate::tools::gui::GUI;
-use crate::traits::command::derive_getters;
+use crate::traits::command::{derive_getters, Params};
 use crate::traits::command::{Command, Runnable};
 use anyhow::Result;
 use std::collections::HashMap;
@@ -7,8 +7,7 @@ use std::str::FromStr;
 use strum::{Display, EnumString};
 
 pub struct Help {
-    pub required_args: Vec<String>,
-    pub params: HashMap<String, String>,
+    pub params: Params,
 }
 
 #[derive(Display, EnumString)]
@@ -22,7 +21,7 @@ enum Actions {
 impl Command for Help {
     derive_getters!();
 
-    fn print_title(&self) -> bool {
+    fn print_title(&self, _action: &str, _params: &[String]) -> bool {
         false
     }
 
@@ -130,7 +129,7 @@ c:;''',;;;;;;;;;;:::cccccccllooooollllllllcclllooodooooooooollcccccccccccccccccc
 }
 
 impl Runnable for Help {
-    fn run(&mut self, action: &String, _params: &[String]) -> Result<()> {
+    fn run(&mut self, action: &str, _params: &[String]) -> Result<()> {
         let action = Actions::from_str(action).unwrap_or(Actions::Default);
 
         match action {
@@ -142,7 +141,7 @@ impl Runnable for Help {
 
 impl Help {
     pub fn show_help(&self) -> Result<()> {
-        GUI::new()
+        GUI::text()
             .title("Welcome to Rusty Search")
             .sub_title("commands:")
             .nl()
@@ -151,8 +150,21 @@ impl Help {
             .content("search:   actions to search index data")
             .content("rank:     actions to set search ranking settings")
             .content("config:   actions to change config settings")
+            .content("serve:    actions to run the http api")
+            .content("completions: actions to print shell completion scripts")
             .nl()
-            .sub_title("Run `{command} help` for specific help")
+            .sub_title("global flags:")
+            .nl()
+            .content("--db {path}        use the database at {path} for this run")
+            .content("--output {format}  print results as text|json|ndjson for this run")
+            .nl()
+            .sub_title("params:")
+            .nl()
+            .content("* params can be given in order, or by name as `--{param} {value}`, `--{param}={value}` or `-{short}`")
+            .content("* flags like {?--create} are set by naming them, values after `--` are always taken in order")
+            .content("* params ending in `...` take every remaining value, e.g. `search query products red shoes`")
+            .nl()
+            .sub_title("Run `{command} help` for specific help, or `{command} {action} --help` for one action")
             .nl();
 
         Ok(())
@@ -162,7 +174,6 @@ impl Help {
 impl Help {
     pub fn new() -> Self {
         Self {
-            required_args: Vec::new(),
             params: HashMap::default(),
         }
     }
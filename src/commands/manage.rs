@@ -1,19 +1,21 @@
+use crate::tools::config::Settings;
 use crate::tools::gui::GUI;
-use crate::tools::validation::StringValidation::{Ignore, SqlColumn, SqlTable};
-use crate::traits::command::{derive_getters, ParamRule};
+use crate::tools::report::{Report, Reported};
+use crate::tools::validation::StringValidation::{Count, Field, Ignore, Json, SqlTable};
+use crate::traits::command::{derive_getters, ActionSpec, ParamRule, Params};
 use crate::traits::command::{Command, Runnable};
-use anyhow::{Ok, Result};
-use rusqlite::Connection;
+use anyhow::{bail, Ok, Result};
+use rusty_search::{BulkMode, BulkOptions, BulkSummary, Format, IndexInfo, KeyCheck, Schema};
+use serde_json::{json, Value};
 use std::collections::HashMap;
 
-use std::fs::remove_file;
+use std::fs::{remove_file, File};
+use std::io::{self, BufWriter};
 use std::str::FromStr;
 use strum_macros::{Display, EnumString};
 
-const DB: &str = "db.db";
-
 pub struct Manage {
-    pub params: HashMap<String, String>,
+    pub params: Params,
 }
 
 #[derive(Display, EnumString, Debug)]
@@ -23,6 +25,16 @@ enum Actions {
     #[strum(ascii_case_insensitive)]
     Init,
     #[strum(ascii_case_insensitive)]
+    Import,
+    #[strum(ascii_case_insensitive)]
+    Export,
+    #[strum(ascii_case_insensitive)]
+    List,
+    #[strum(ascii_case_insensitive)]
+    Info,
+    #[strum(ascii_case_insensitive)]
+    Key,
+    #[strum(ascii_case_insensitive)]
     Delete,
     #[strum(ascii_case_insensitive)]
     Purge,
@@ -35,15 +47,88 @@ impl Manage {}
 impl Command for Manage {
     derive_getters!();
 
+    fn actions(&self) -> Vec<ActionSpec> {
+        vec![
+            ActionSpec {
+                name: "create",
+                rules: Manage::rules(&Actions::Create),
+                description:
+                    "Create an empty index called {index}, with the field types in {schema}",
+            },
+            ActionSpec {
+                name: "init",
+                rules: Manage::rules(&Actions::Init),
+                description: "Create table called {index}, set {key} and populate with {data}",
+            },
+            ActionSpec {
+                name: "import",
+                rules: Manage::rules(&Actions::Import),
+                description: "Add the records in {source} to {index}, creating it if missing",
+            },
+            ActionSpec {
+                name: "export",
+                rules: Manage::rules(&Actions::Export),
+                description: "Write the entries of {index}, or those matching {query}, to {target}",
+            },
+            ActionSpec {
+                name: "list",
+                rules: Manage::rules(&Actions::List),
+                description: "Show every index with its key field, entries and size",
+            },
+            ActionSpec {
+                name: "info",
+                rules: Manage::rules(&Actions::Info),
+                description: "Show the catalog entry, schema and ranking settings of {index}",
+            },
+            ActionSpec {
+                name: "key",
+                rules: Manage::rules(&Actions::Key),
+                description:
+                    "Set the key field of {index} and what happens when entries don't match it",
+            },
+            ActionSpec {
+                name: "delete",
+                rules: Manage::rules(&Actions::Delete),
+                description: "Delete index and data",
+            },
+            ActionSpec {
+                name: "purge",
+                rules: Manage::rules(&Actions::Purge),
+                description: "Delete database",
+            },
+        ]
+    }
+
+    // exports to stdout only print the exported data
+    fn print_title(&self, action: &str, params: &[String]) -> bool {
+        let mut export = Manage::new();
+
+        !matches!(Actions::from_str(action), Result::Ok(Actions::Export))
+            || export
+                .assert_params(Manage::rules(&Actions::Export), params)
+                .is_err()
+            || Manage::export_target(export.get_param_opt("target")).is_some()
+    }
+
     fn help(&self) -> Result<()> {
-        GUI::new()
+        GUI::text()
             .title("Manage Command")
             .sub_title("actions:")
             .nl()
-            .content("create: {index} {key}             | create table called {index} and set {key}")
-            .content("init:   {index} {key} {data}      | Create table called {index}, set {key} and populate with {data}")
-            .content("delete: {index}                   | Delete index and data")
-            .content("purge:                            | Delete database")
+            .actions(&self.actions())
+            .nl()
+            .content("* {source} is a file path, or `-` to read from stdin")
+            .content("* {?format} is json|ndjson|csv, by default it's taken from the file extension")
+            .content("* csv files need a header row, its names become the fields of each record, empty cells are left out")
+            .content("* records with a key that already exists are skipped")
+            .content("* {?mode} is atomic|continue, atomic loads roll back everything when a record fails")
+            .content("* continue loads commit every {?batch} records and list the records that failed")
+            .content("* init defaults to atomic and import to continue, {?batch} defaults to 1000")
+            .content("* {?target} is a file path, or `-` for stdout which is the default")
+            .content("* csv exports have a column for each top level field, nested values are written as json")
+            .content("* {?schema} maps fields to text|keyword|integer|float|bool|date|geo, e.g. `{\"title\": \"text\", \"price\": \"float\"}`")
+            .content("* a field can also be `{\"type\": ..}` with indexed, stored, filterable or sortable set to true|false")
+            .content("* entries that don't match the schema are rejected, fields it doesn't declare are left as they are")
             .nl();
 
         Ok(())
@@ -51,13 +136,18 @@ impl Command for Manage {
 }
 
 impl Runnable for Manage {
-    fn run(&mut self, action: &String, params: &[String]) -> Result<()> {
+    fn run(&mut self, action: &str, params: &[String]) -> Result<()> {
         let action = Actions::from_str(action).unwrap_or(Actions::Help);
 
         match action {
-            Actions::Create => self.create_table(params, true)?,
+            Actions::Create => self.create_table(params)?,
             Actions::Init => self.init_index(params)?,
             Actions::Help => self.help()?,
+            Actions::Import => self.import(params)?,
+            Actions::Export => self.export(params)?,
+            Actions::List => self.list(params)?,
+            Actions::Info => self.info(params)?,
+            Actions::Key => self.key(params)?,
             Actions::Delete => self.delete_index(params)?,
             Actions::Purge => Manage::purge_db()?,
         }
@@ -73,113 +163,489 @@ impl Manage {
         }
     }
 
-    fn create_table(&mut self, params: &[String], output: bool) -> Result<()> {
-        self.assert_params(
-            vec![ParamRule {
-                key: "index",
-                validation: SqlTable,
-                required: &true,
-            }],
-            params,
-        )?;
-
-        GUI::new().print_params(self as &dyn Command);
-
-        let connection = Connection::open(DB)?;
-
-        connection.execute_batch(&format!(
-            "DROP TABLE if exists `{table}`;
-            CREATE TABLE `{table}` (
-                `key` TEXT PRIMARY KEY,
-                `data` TEXT);
-            ",
-            table = self.get_param("index")
-        ))?;
-
-        if output {
-            GUI::new()
-                .sub_title("result:")
-                .content(&format!(
-                    "Success: `{}` Index Created/Exists",
-                    self.get_param("index")
-                ))
-                .nl();
-        }
-
-        Ok(())
-    }
-
-    fn init_index(&mut self, params: &[String]) -> Result<()> {
-        self.assert_params(
-            vec![
+    fn rules(action: &Actions) -> Vec<ParamRule<'static>> {
+        match action {
+            Actions::Create => vec![
+                ParamRule {
+                    key: "index",
+                    validation: SqlTable,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "schema",
+                    validation: Json,
+                    required: &false,
+                    short: Some('s'),
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "key",
+                    validation: Field,
+                    required: &false,
+                    short: Some('k'),
+                    ..Default::default()
+                },
+            ],
+            Actions::Init => vec![
                 ParamRule {
                     key: "index",
                     validation: SqlTable,
                     required: &true,
+                    ..Default::default()
                 },
                 ParamRule {
                     key: "key",
-                    validation: SqlColumn,
+                    validation: Field,
                     required: &true,
+                    ..Default::default()
                 },
                 ParamRule {
                     key: "data",
+                    validation: Json,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "mode",
+                    validation: Ignore,
+                    required: &false,
+                    choices: &["atomic", "continue"],
+                    short: Some('m'),
+                    default: Some("atomic"),
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "batch",
+                    validation: Count,
+                    required: &false,
+                    short: Some('b'),
+                    default: Some("1000"),
+                    ..Default::default()
+                },
+            ],
+            Actions::Import => vec![
+                ParamRule {
+                    key: "index",
+                    validation: SqlTable,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "key",
+                    validation: Field,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "source",
                     validation: Ignore,
                     required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "format",
+                    validation: Ignore,
+                    required: &false,
+                    choices: &["json", "ndjson", "jsonl", "csv"],
+                    short: Some('f'),
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "mode",
+                    validation: Ignore,
+                    required: &false,
+                    choices: &["atomic", "continue"],
+                    short: Some('m'),
+                    default: Some("continue"),
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "batch",
+                    validation: Count,
+                    required: &false,
+                    short: Some('b'),
+                    default: Some("1000"),
+                    ..Default::default()
                 },
             ],
-            params,
-        )?;
+            Actions::Export => vec![
+                ParamRule {
+                    key: "index",
+                    validation: SqlTable,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "target",
+                    validation: Ignore,
+                    required: &false,
+                    short: Some('t'),
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "format",
+                    validation: Ignore,
+                    required: &false,
+                    choices: &["json", "ndjson", "jsonl", "csv"],
+                    short: Some('f'),
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "query",
+                    validation: Ignore,
+                    required: &false,
+                    short: Some('q'),
+                    repeated: true,
+                    ..Default::default()
+                },
+            ],
+            Actions::Key => vec![
+                ParamRule {
+                    key: "index",
+                    validation: SqlTable,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "field",
+                    validation: Field,
+                    required: &false,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "check",
+                    validation: Ignore,
+                    required: &false,
+                    choices: &["off", "warn", "reject"],
+                    ..Default::default()
+                },
+            ],
+            Actions::Info | Actions::Delete => vec![ParamRule {
+                key: "index",
+                validation: SqlTable,
+                required: &true,
+                ..Default::default()
+            }],
+            _ => vec![],
+        }
+    }
 
-        GUI::new().print_params(self as &dyn Command);
+    fn create_table(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Manage::rules(&Actions::Create), params)?;
 
-        self.create_table(params, false)?;
+        GUI::new().print_params(self as &dyn Command);
 
-        let conn = Connection::open(DB)?;
-        let mut stmt = conn.prepare(&format!(
-            "INSERT INTO `{table}` (`key`, `data`) values (?1, ?2)",
-            table = self.get_param("index")
-        ))?;
+        let schema = match self.get_param_opt("schema") {
+            Some(schema) => Some(Schema::from_json(&serde_json::from_str(schema)?)?),
+            None => None,
+        };
 
-        let products: Vec<HashMap<String, String>> = serde_json::from_str(self.get_param("data"))?;
+        let engine = Settings::engine()?;
+        let index = engine.create_index(self.get_param("index"))?;
 
-        for prod in products {
-            stmt.execute([
-                &prod.get(self.get_param("key")).unwrap(),
-                &serde_json::to_string(&prod).unwrap(),
-            ])?;
+        if let Some(schema) = &schema {
+            index.set_schema(schema)?;
+        }
+        if let Some(key) = self.get_param_opt("key") {
+            index.set_key_field(key)?;
         }
 
-        GUI::new().sub_title("result:").content("Success").nl();
+        GUI::new().report(
+            &Report::success(&format!(
+                "`{}` Index Created/Exists",
+                self.get_param("index")
+            ))
+            .index(self.get_param("index"))
+            .with_data(json!({ "schema": index.schema()?.to_json() })),
+        );
 
         Ok(())
     }
 
-    fn delete_index(&mut self, params: &[String]) -> Result<()> {
-        self.assert_params(
-            vec![ParamRule {
-                key: "index",
-                validation: SqlTable,
-                required: &true,
-            }],
-            params,
+    fn init_index(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Manage::rules(&Actions::Init), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let products: Vec<Value> = serde_json::from_str(self.get_param("data"))?;
+
+        let summary = Settings::engine()?.init_index(
+            self.get_param("index"),
+            self.get_param("key"),
+            &products,
+            self.bulk_options()?,
         )?;
 
+        let message = match summary.failed {
+            0 => format!("{} entries added", summary.inserted),
+            failed => format!("{} entries added, {} failed", summary.inserted, failed),
+        };
+
+        self.print_summary(&message, &summary)
+    }
+
+    fn import(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Manage::rules(&Actions::Import), params)?;
+
         GUI::new().print_params(self as &dyn Command);
 
-        let conn = Connection::open(DB)?;
-        let result = match conn.execute(
-            &format!("DROP TABLE `{table}`", table = self.get_param("index")),
-            [],
-        ) {
-            Err(err) => "Error: ".to_string() + &err.to_string(),
-            _ => format!(
-                "Success: deleted index '{index}'",
-                index = self.get_param("index"),
+        let source = self.get_param("source");
+        let format = match self.get_param_opt("format") {
+            Some(format) => Manage::format(format)?,
+            None => match Format::from_path(source) {
+                Some(format) => format,
+                None => bail!(
+                    "Can't tell the format of '{}', expected json|ndjson|csv",
+                    source
+                ),
+            },
+        };
+
+        let options = self.bulk_options()?;
+        let engine = Settings::engine()?;
+        let created = !engine.has_index(self.get_param("index"))?;
+
+        if created {
+            engine.create_index(self.get_param("index"))?;
+        }
+
+        let index = engine.index(self.get_param("index"))?;
+        let key = self.get_param("key");
+
+        let summary = match source {
+            "-" => index.import(io::stdin().lock(), format, key, options),
+            path => File::open(path)
+                .map_err(rusty_search::Error::from)
+                .and_then(|file| index.import(file, format, key, options)),
+        };
+
+        // an index made for a failed import is removed when nothing was
+        // committed to it, batches committed in continue mode are kept
+        let summary = match summary {
+            Err(err) if created && index.info()?.documents == 0 => {
+                engine.delete_index(self.get_param("index"))?;
+                return Err(err.into());
+            }
+            summary => summary?,
+        };
+
+        self.print_summary(
+            &format!(
+                "{inserted} inserted, {skipped} skipped, {failed} failed",
+                inserted = summary.inserted,
+                skipped = summary.skipped,
+                failed = summary.failed
             ),
+            &summary,
+        )
+    }
+
+    fn export(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Manage::rules(&Actions::Export), params)?;
+
+        let target = Manage::export_target(self.get_param_opt("target"));
+        let format = match (self.get_param_opt("format"), target) {
+            (Some(format), _) => Manage::format(format)?,
+            (None, Some(path)) => match Format::from_path(path) {
+                Some(format) => format,
+                None => bail!(
+                    "Can't tell the format of '{}', expected json|ndjson|csv",
+                    path
+                ),
+            },
+            (None, None) => Format::Json,
+        };
+
+        let engine = Settings::engine()?;
+        let index = engine.index(self.get_param("index"))?;
+        let query = match self.get_param_values("query") {
+            [] => None,
+            terms => Some(terms.join(" ")),
+        };
+        let query = query.as_deref();
+
+        let path = match target {
+            Some(path) => path,
+            None => {
+                index.export(io::stdout().lock(), format, query)?;
+
+                return Ok(());
+            }
         };
 
-        GUI::new().sub_title("result:").content(&result).nl();
+        GUI::new().print_params(self as &dyn Command);
+
+        let count = index.export(BufWriter::new(File::create(path)?), format, query)?;
+
+        GUI::new().report(
+            &Report::success(&format!("{} entries exported to {}", count, path))
+                .index(self.get_param("index"))
+                .count(count)
+                .with_data(json!({ "target": path, "format": format.to_string() })),
+        );
+
+        Ok(())
+    }
+
+    fn list(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Manage::rules(&Actions::List), params)?;
+
+        let indexes = Settings::engine()?.indexes()?;
+
+        GUI::new().report(
+            &Report::data(Value::Array(
+                indexes.iter().map(Manage::info_json).collect(),
+            ))
+            .count(indexes.len()),
+        );
+
+        GUI::new().sub_title("indexes:");
+
+        for info in indexes.iter() {
+            GUI::new().content(&format!(
+                "{name}: {documents} entries, key {key}, {size}, updated {updated}",
+                name = info.name,
+                documents = info.documents,
+                key = info.key_field.as_deref().unwrap_or("unknown"),
+                size = Manage::size(info.size),
+                updated = info.updated.as_deref().unwrap_or("unknown")
+            ));
+        }
+
+        GUI::new().nl();
+
+        Ok(())
+    }
+
+    fn info(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Manage::rules(&Actions::Info), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let engine = Settings::engine()?;
+        let index = engine.index(self.get_param("index"))?;
+        let info = index.info()?;
+        let schema = index.schema()?;
+        let rank = index.rank_settings()?;
+
+        let mut data = Manage::info_json(&info);
+        data["schema"] = schema.to_json();
+        data["rank"] = json!({
+            "weights": rank.weights.iter()
+                .map(|(field, weight)| json!({ "field": field, "weight": weight }))
+                .collect::<Vec<Value>>(),
+            "boosts": rank.boosts.iter()
+                .map(|(field, factor)| json!({ "field": field, "factor": factor }))
+                .collect::<Vec<Value>>(),
+            "sorts": rank.sorts.iter()
+                .map(|(field, direction)| json!({ "field": field, "direction": direction.to_string() }))
+                .collect::<Vec<Value>>(),
+        });
+
+        GUI::new().report(&Report::data(data).index(&info.name));
+
+        let unknown = |value: &Option<String>| value.clone().unwrap_or(String::from("unknown"));
+
+        GUI::new()
+            .sub_title("index:")
+            .content(&format!("key field: {}", unknown(&info.key_field)))
+            .content(&format!("key check: {}", info.key_check))
+            .content(&format!("entries:   {}", info.documents))
+            .content(&format!("size:      {}", Manage::size(info.size)))
+            .content(&format!("created:   {}", unknown(&info.created)))
+            .content(&format!("updated:   {}", unknown(&info.updated)))
+            .nl()
+            .sub_title("schema:");
+
+        for field in schema.fields.iter() {
+            let flags: Vec<&str> = [
+                (field.indexed, "indexed"),
+                (field.stored, "stored"),
+                (field.filterable, "filterable"),
+                (field.sortable, "sortable"),
+            ]
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, flag)| *flag)
+            .collect();
+
+            GUI::new().content(&format!(
+                "{}: {} ({})",
+                field.name,
+                field.field_type,
+                flags.join(", ")
+            ));
+        }
+
+        GUI::new().nl().sub_title("ranking:");
+
+        for (field, weight) in rank.weights.iter() {
+            GUI::new().content(&format!("weight {}: {}", field, weight));
+        }
+        for (field, factor) in rank.boosts.iter() {
+            GUI::new().content(&format!("boost {}: {}", field, factor));
+        }
+        for (field, direction) in rank.sorts.iter() {
+            GUI::new().content(&format!("sort {}: {}", field, direction));
+        }
+
+        GUI::new().nl();
+
+        Ok(())
+    }
+
+    // without a field or check it only shows the current ones
+    fn key(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Manage::rules(&Actions::Key), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let engine = Settings::engine()?;
+        let index = engine.index(self.get_param("index"))?;
+
+        if let Some(field) = self.get_param_opt("field") {
+            index.set_key_field(field)?;
+        }
+        if let Some(check) = self.get_param_opt("check") {
+            index.set_key_check(KeyCheck::from_str(check)?)?;
+        }
+
+        let key_field = index.key_field()?;
+        let key_check = index.key_check()?;
+
+        GUI::new().report(
+            &Report::success(&format!(
+                "key field of '{index}' is {field}, mismatched entries {check}",
+                index = self.get_param("index"),
+                field = key_field.as_deref().unwrap_or("not set"),
+                check = match key_check {
+                    KeyCheck::Off => "are allowed",
+                    KeyCheck::Warn => "give a warning",
+                    KeyCheck::Reject => "are rejected",
+                }
+            ))
+            .index(self.get_param("index"))
+            .with_data(json!({ "key_field": key_field, "key_check": key_check.to_string() })),
+        );
+
+        Ok(())
+    }
+
+    fn delete_index(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Manage::rules(&Actions::Delete), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        Settings::engine()?.delete_index(self.get_param("index"))?;
+
+        GUI::new().report(
+            &Report::success(&format!(
+                "deleted index '{index}'",
+                index = self.get_param("index"),
+            ))
+            .index(self.get_param("index")),
+        );
 
         Ok(())
     }
@@ -187,13 +653,107 @@ impl Manage {
     fn purge_db() -> Result<()> {
         GUI::new().title("Running 'Purge'");
 
-        remove_file("db.db")?;
+        remove_file(&Settings::get().db)?;
 
-        GUI::new()
-            .sub_title("result:")
-            .content("Success: Database has been deleted")
-            .nl();
+        GUI::new().report(&Report::success("Database has been deleted"));
 
         Ok(())
     }
 }
+
+impl Manage {
+    fn format(format: &str) -> Result<Format> {
+        match Format::from_str(format) {
+            Result::Ok(format) => Ok(format),
+            Err(_) => bail!("Invalid value for format, expected json|ndjson|csv"),
+        }
+    }
+
+    fn bulk_options(&self) -> Result<BulkOptions> {
+        let mut options = BulkOptions::default();
+
+        if let Some(mode) = self.get_param_opt("mode") {
+            options.mode = match BulkMode::from_str(mode) {
+                Result::Ok(mode) => mode,
+                Err(_) => bail!("Invalid value for mode, expected atomic|continue"),
+            };
+        }
+
+        if let Some(batch) = self.get_param_opt("batch") {
+            options.batch_size = batch.parse()?;
+        }
+
+        Ok(options)
+    }
+
+    // a warning when some records failed and an error when every one did
+    fn print_summary(&self, message: &str, summary: &BulkSummary) -> Result<()> {
+        let failures: Vec<Value> = summary
+            .failures
+            .iter()
+            .map(|(record, reason)| json!({ "record": record, "error": reason }))
+            .collect();
+
+        let failed = summary.failed > 0 && summary.inserted == 0 && summary.skipped == 0;
+        let report = match (summary.failed, failed) {
+            (0, _) => Report::success(message),
+            (_, false) => Report::warning(message),
+            (_, true) => Report::error(message),
+        };
+
+        GUI::new().report(
+            &report
+                .index(self.get_param("index"))
+                .count(summary.inserted)
+                .with_data(json!({
+                    "inserted": summary.inserted,
+                    "skipped": summary.skipped,
+                    "failed": summary.failed,
+                    "failures": failures,
+                })),
+        );
+
+        if !summary.failures.is_empty() {
+            GUI::new().sub_title("failures:");
+
+            for (record, reason) in summary.failures.iter() {
+                GUI::new().content(&format!("record {}: {}", record, reason));
+            }
+
+            GUI::new().nl();
+        }
+
+        match failed {
+            true => Err(Reported.into()),
+            false => Ok(()),
+        }
+    }
+
+    fn info_json(info: &IndexInfo) -> Value {
+        json!({
+            "name": info.name,
+            "key_field": info.key_field,
+            "key_check": info.key_check.to_string(),
+            "documents": info.documents,
+            "size": info.size,
+            "created": info.created,
+            "updated": info.updated,
+        })
+    }
+
+    fn size(bytes: u64) -> String {
+        match bytes {
+            0..=1023 => format!("{} B", bytes),
+            1024..=1048575 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+            _ => format!("{:.1} MiB", bytes as f64 / 1048576.0),
+        }
+    }
+
+    // `-` or no target at all is stdout
+    fn export_target(target: Option<&str>) -> Option<&str> {
+        match target {
+            None | Some("-") => None,
+            Some(path) => Some(path),
+        }
+    }
+}
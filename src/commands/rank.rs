@@ -0,0 +1,286 @@
+use crate::tools::config::Settings;
+use crate::tools::gui::GUI;
+use crate::tools::report::Report;
+use crate::tools::validation::StringValidation::{self, Field, Ignore, Number, SqlTable};
+use crate::traits::command::{derive_getters, ActionSpec, ParamRule, Params};
+use crate::traits::command::{Command, Runnable};
+use anyhow::{bail, Ok, Result};
+use rusty_search::{Direction, Rule};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+use strum_macros::{Display, EnumString};
+
+pub struct Rank {
+    pub params: Params,
+}
+
+#[derive(Display, EnumString, Debug)]
+enum Actions {
+    #[strum(ascii_case_insensitive)]
+    Weight,
+    #[strum(ascii_case_insensitive)]
+    Boost,
+    #[strum(ascii_case_insensitive)]
+    Sort,
+    #[strum(ascii_case_insensitive)]
+    List,
+    #[strum(ascii_case_insensitive)]
+    Reset,
+    #[strum(ascii_case_insensitive)]
+    Help,
+}
+
+impl Command for Rank {
+    derive_getters!();
+
+    fn actions(&self) -> Vec<ActionSpec> {
+        vec![
+            ActionSpec {
+                name: "weight",
+                rules: Rank::rules(&Actions::Weight),
+                description: "Multiply the relevance of matches in {field} by {weight}",
+            },
+            ActionSpec {
+                name: "boost",
+                rules: Rank::rules(&Actions::Boost),
+                description: "Add {factor} x the numeric value of {field} to the relevance",
+            },
+            ActionSpec {
+                name: "sort",
+                rules: Rank::rules(&Actions::Sort),
+                description: "Break relevance ties by {field}, {direction} is asc|desc",
+            },
+            ActionSpec {
+                name: "list",
+                rules: Rank::rules(&Actions::List),
+                description: "Show the ranking settings of {index}",
+            },
+            ActionSpec {
+                name: "reset",
+                rules: Rank::rules(&Actions::Reset),
+                description: "Remove all ranking settings, or only those for {field}",
+            },
+        ]
+    }
+
+    fn help(&self) -> Result<()> {
+        GUI::text()
+            .title("Rank Command")
+            .sub_title("actions:")
+            .nl()
+            .actions(&self.actions())
+            .nl()
+            .content("* fields default to a weight of 1, setting a weight of 1 or a boost of 0 removes it")
+            .content("* weights have to be above 0, boosts can be negative to rank high values lower")
+            .content("* ties are broken by sort fields in the order they were added")
+            .nl();
+
+        Ok(())
+    }
+}
+
+impl Runnable for Rank {
+    fn run(&mut self, action: &str, params: &[String]) -> Result<()> {
+        let action = Actions::from_str(action).unwrap_or(Actions::Help);
+
+        match action {
+            Actions::Weight => self.set_rule(params, Rule::Weight)?,
+            Actions::Boost => self.set_rule(params, Rule::Boost)?,
+            Actions::Sort => self.set_rule(params, Rule::Sort)?,
+            Actions::List => self.list(params)?,
+            Actions::Reset => self.reset(params)?,
+            Actions::Help => self.help()?,
+        }
+
+        Ok(())
+    }
+}
+
+impl Rank {
+    pub fn new() -> Self {
+        Self {
+            params: HashMap::default(),
+        }
+    }
+
+    fn rules(action: &Actions) -> Vec<ParamRule<'static>> {
+        match action {
+            Actions::Weight => Rank::rule_params(Rule::Weight),
+            Actions::Boost => Rank::rule_params(Rule::Boost),
+            Actions::Sort => Rank::rule_params(Rule::Sort),
+            Actions::List => vec![ParamRule {
+                key: "index",
+                validation: SqlTable,
+                required: &true,
+                ..Default::default()
+            }],
+            Actions::Reset => vec![
+                ParamRule {
+                    key: "index",
+                    validation: SqlTable,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "field",
+                    validation: Field,
+                    required: &false,
+                    ..Default::default()
+                },
+            ],
+            _ => vec![],
+        }
+    }
+
+    // the name and validation of the value each rule is set to
+    fn value_param(rule: Rule) -> (&'static str, StringValidation) {
+        match rule {
+            Rule::Weight => ("weight", Number),
+            Rule::Boost => ("factor", Number),
+            Rule::Sort => ("direction", Ignore),
+        }
+    }
+
+    fn rule_params(rule: Rule) -> Vec<ParamRule<'static>> {
+        let (key, validation) = Rank::value_param(rule);
+
+        vec![
+            ParamRule {
+                key: "index",
+                validation: SqlTable,
+                required: &true,
+                ..Default::default()
+            },
+            ParamRule {
+                key: "field",
+                validation: Field,
+                required: &true,
+                ..Default::default()
+            },
+            ParamRule {
+                key,
+                validation,
+                required: &true,
+                choices: match rule {
+                    Rule::Sort => &["asc", "desc"],
+                    _ => &[],
+                },
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn set_rule(&mut self, params: &[String], rule: Rule) -> Result<()> {
+        let (key, _) = Rank::value_param(rule);
+
+        self.assert_params(Rank::rule_params(rule), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let engine = Settings::engine()?;
+        let index = engine.index(self.get_param("index"))?;
+        let field = self.get_param("field");
+
+        let value = match rule {
+            Rule::Weight => {
+                let weight: f64 = self.get_param(key).parse()?;
+                index.set_weight(field, weight)?;
+                weight.to_string()
+            }
+            Rule::Boost => {
+                let factor: f64 = self.get_param(key).parse()?;
+                index.set_boost(field, factor)?;
+                factor.to_string()
+            }
+            Rule::Sort => {
+                let direction = match Direction::from_str(self.get_param(key)) {
+                    Result::Ok(direction) => direction,
+                    Err(_) => bail!("Invalid value for direction, expected asc|desc"),
+                };
+                index.set_sort(field, direction)?;
+                direction.to_string()
+            }
+        };
+
+        GUI::new().report(
+            &Report::success(&format!(
+                "{rule} for {index}->{field} set to {value}",
+                rule = rule,
+                index = self.get_param("index"),
+                field = field,
+                value = value
+            ))
+            .index(self.get_param("index"))
+            .with_data(json!({ "rule": rule.to_string(), "field": field, "value": value })),
+        );
+
+        Ok(())
+    }
+
+    fn list(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Rank::rules(&Actions::List), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let engine = Settings::engine()?;
+        let settings = engine.index(self.get_param("index"))?.rank_settings()?;
+
+        GUI::new().report(
+            &Report::data(json!({
+                "weights": settings.weights.iter()
+                    .map(|(field, weight)| json!({ "field": field, "weight": weight }))
+                    .collect::<Vec<Value>>(),
+                "boosts": settings.boosts.iter()
+                    .map(|(field, factor)| json!({ "field": field, "factor": factor }))
+                    .collect::<Vec<Value>>(),
+                "sorts": settings.sorts.iter()
+                    .map(|(field, direction)| json!({ "field": field, "direction": direction.to_string() }))
+                    .collect::<Vec<Value>>(),
+            }))
+            .index(self.get_param("index")),
+        );
+
+        GUI::new().sub_title("weights:");
+        for (field, weight) in settings.weights.iter() {
+            GUI::new().content(&format!("{}: {}", field, weight));
+        }
+
+        GUI::new().nl().sub_title("boosts:");
+        for (field, factor) in settings.boosts.iter() {
+            GUI::new().content(&format!("{}: {}", field, factor));
+        }
+
+        GUI::new().nl().sub_title("sorts:");
+        for (field, direction) in settings.sorts.iter() {
+            GUI::new().content(&format!("{}: {}", field, direction));
+        }
+
+        GUI::new().nl();
+
+        Ok(())
+    }
+
+    fn reset(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Rank::rules(&Actions::Reset), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let engine = Settings::engine()?;
+        let removed = engine
+            .index(self.get_param("index"))?
+            .reset_rank(self.get_param_opt("field"))?;
+
+        GUI::new().report(
+            &Report::success(&format!(
+                "{removed} ranking settings removed from '{index}'",
+                removed = removed,
+                index = self.get_param("index")
+            ))
+            .index(self.get_param("index"))
+            .count(removed),
+        );
+
+        Ok(())
+    }
+}
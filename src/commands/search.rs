@@ -0,0 +1,435 @@
+use crate::tools::config::Settings;
+use crate::tools::gui::GUI;
+use crate::tools::report::Report;
+use crate::tools::validation::StringValidation::{
+    Bool, Count, Fields, Filter, Ignore, Offset, Sort, SqlTable,
+};
+use crate::traits::command::{derive_getters, ActionSpec, ParamRule, Params};
+use crate::traits::command::{Command, Runnable};
+use anyhow::{Ok, Result};
+use rusty_search::{
+    parse_sort, Facet, FacetCounts, Filter as SearchFilter, Fuzzy, Hit, Metric, SearchOptions,
+};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+use strum_macros::{Display, EnumString};
+
+pub struct Search {
+    pub params: Params,
+}
+
+#[derive(Display, EnumString, Debug)]
+enum Actions {
+    #[strum(ascii_case_insensitive)]
+    Query,
+    #[strum(ascii_case_insensitive)]
+    Suggest,
+    #[strum(ascii_case_insensitive)]
+    Sync,
+    #[strum(ascii_case_insensitive)]
+    Help,
+}
+
+impl Command for Search {
+    derive_getters!();
+
+    fn actions(&self) -> Vec<ActionSpec> {
+        vec![
+            ActionSpec {
+                name: "query",
+                rules: Search::rules(&Actions::Query),
+                description:
+                    "Search {index} for entries matching every term in {query} and the filter",
+            },
+            ActionSpec {
+                name: "suggest",
+                rules: Search::rules(&Actions::Suggest),
+                description: "Complete the last word of {query} with words from {index} that still find entries",
+            },
+            ActionSpec {
+                name: "sync",
+                rules: Search::rules(&Actions::Sync),
+                description: "Rebuild the search table of {index} from its data",
+            },
+        ]
+    }
+
+    fn help(&self) -> Result<()> {
+        GUI::text()
+            .title("Search Command")
+            .sub_title("actions:")
+            .nl()
+            .actions(&self.actions())
+            .nl()
+            .content("* terms can be limited to a field with `field:term`, e.g. `title:shoes brand:nike`")
+            .content("* quote phrases with double quotes, e.g. `title:\"red shoes\"`")
+            .content("* {?--filter|-f} keeps entries whose fields match, without a {query} it lists every one")
+            .content("  e.g. `price < 50 AND brand IN (\"nike\", \"adidas\") AND in_stock = true`")
+            .content("* filters compare with = != < <= > >=, check lists with IN and NOT IN, check a field is")
+            .content("  there with EXISTS and combine with AND, OR, NOT and brackets")
+            .content("* an array field matches when any of its items does, `field = null` matches missing fields")
+            .content("* {?--facets} counts the values of fields like `brand,category` over every match, numeric")
+            .content("  fields are counted in ranges")
+            .content("* {?--sort|-s} orders by fields like `price:asc,rating:desc` before relevance")
+            .content("* {?--limit|-l} hits are shown at a time, page on with {?--offset} or pass the key shown as")
+            .content("  next to {?--after}")
+            .content("* {?--fuzzy|-z} lets words match others up to that many edits away, fewer for short words,")
+            .content("  counted by {?--metric}. entries matching more words as typed come first")
+            .content("* {?--prefix|-p} also matches words that start with the last term, for search as you type")
+            .content("* suggest shows {?count|-n} completions of the last word, each with {?limit|-l} of its entries")
+            .content("* indexes made before prefix search need a sync to make it fast")
+            .content("* json output has the hits as `data` and `total`, `next` and `facets` in `summary`,")
+            .content("  ndjson prints a line per hit and then the report")
+            .nl();
+
+        Ok(())
+    }
+}
+
+impl Runnable for Search {
+    fn run(&mut self, action: &str, params: &[String]) -> Result<()> {
+        let action = Actions::from_str(action).unwrap_or(Actions::Help);
+
+        match action {
+            Actions::Query => self.query(params)?,
+            Actions::Suggest => self.suggest(params)?,
+            Actions::Sync => self.sync(params)?,
+            Actions::Help => self.help()?,
+        }
+
+        Ok(())
+    }
+}
+
+impl Search {
+    pub fn new() -> Self {
+        Self {
+            params: HashMap::default(),
+        }
+    }
+
+    fn rules(action: &Actions) -> Vec<ParamRule<'static>> {
+        match action {
+            Actions::Query => vec![
+                ParamRule {
+                    key: "index",
+                    validation: SqlTable,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "query",
+                    validation: Ignore,
+                    required: &false,
+                    repeated: true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "filter",
+                    validation: Filter,
+                    required: &false,
+                    short: Some('f'),
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "facets",
+                    validation: Fields,
+                    required: &false,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "sort",
+                    validation: Sort,
+                    required: &false,
+                    short: Some('s'),
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "limit",
+                    validation: Count,
+                    required: &false,
+                    short: Some('l'),
+                    default: Some("20"),
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "offset",
+                    validation: Offset,
+                    required: &false,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "after",
+                    validation: Ignore,
+                    required: &false,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "fuzzy",
+                    validation: Count,
+                    required: &false,
+                    short: Some('z'),
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "metric",
+                    validation: Ignore,
+                    required: &false,
+                    default: Some("damerau"),
+                    choices: &["damerau", "levenshtein"],
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "prefix",
+                    validation: Bool,
+                    required: &false,
+                    short: Some('p'),
+                    flag: true,
+                    ..Default::default()
+                },
+            ],
+            Actions::Suggest => vec![
+                ParamRule {
+                    key: "index",
+                    validation: SqlTable,
+                    required: &true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "query",
+                    validation: Ignore,
+                    required: &true,
+                    repeated: true,
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "filter",
+                    validation: Filter,
+                    required: &false,
+                    short: Some('f'),
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "count",
+                    validation: Count,
+                    required: &false,
+                    short: Some('n'),
+                    default: Some("5"),
+                    ..Default::default()
+                },
+                ParamRule {
+                    key: "limit",
+                    validation: Count,
+                    required: &false,
+                    short: Some('l'),
+                    default: Some("3"),
+                    ..Default::default()
+                },
+            ],
+            Actions::Sync => vec![ParamRule {
+                key: "index",
+                validation: SqlTable,
+                required: &true,
+                ..Default::default()
+            }],
+            _ => vec![],
+        }
+    }
+
+    fn query(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Search::rules(&Actions::Query), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let engine = Settings::engine()?;
+        let options = self.search_options()?;
+        let index = engine.index(self.get_param("index"))?;
+        let query = self.get_param_values("query").join(" ");
+        let results = index.search(&query, &options)?;
+
+        let hits: Value = results.hits.iter().map(Hit::to_json).collect();
+
+        let facets = match self.get_param_opt("facets") {
+            Some(fields) => {
+                let fields: Vec<String> = fields.split(',').map(str::to_string).collect();
+                Some(index.facets(&query, &options, &fields)?)
+            }
+            None => None,
+        };
+
+        // `data` is always the hits, `facets` is null unless asked for
+        let summary = json!({
+            "total": results.total,
+            "next": results.next,
+            "facets": facets.as_ref().map(|facets| {
+                facets
+                    .iter()
+                    .map(|facet| (facet.field.to_string(), facet.to_json()))
+                    .collect::<Map<String, Value>>()
+            }),
+        });
+
+        GUI::new().report(
+            &Report::success(&format!(
+                "{total} matching entries, showing {count}",
+                total = results.total,
+                count = results.hits.len()
+            ))
+            .index(self.get_param("index"))
+            .count(results.hits.len())
+            .with_data(hits)
+            .with_summary(summary),
+        );
+
+        if !results.hits.is_empty() {
+            GUI::new().sub_title("entries:");
+
+            for hit in results.hits.iter() {
+                GUI::new().content(&format!(
+                    "{key} (v{version}): {data}",
+                    key = hit.key,
+                    version = hit.version,
+                    data = hit.document
+                ));
+            }
+
+            GUI::new().nl();
+        }
+
+        if let Some(next) = &results.next {
+            GUI::new()
+                .content(&format!("more with `--after {}`", next))
+                .nl();
+        }
+
+        if let Some(facets) = facets {
+            Search::print_facets(&facets);
+        }
+
+        Ok(())
+    }
+
+    fn suggest(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Search::rules(&Actions::Suggest), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let engine = Settings::engine()?;
+        let suggestions = engine.index(self.get_param("index"))?.suggest(
+            &self.get_param_values("query").join(" "),
+            &self.search_options()?,
+            self.get_param("count").parse()?,
+        )?;
+
+        let data = suggestions
+            .iter()
+            .map(|suggestion| {
+                json!({
+                    "query": suggestion.query,
+                    "total": suggestion.total,
+                    "hits": suggestion.hits.iter().map(Hit::to_json).collect::<Value>(),
+                })
+            })
+            .collect();
+
+        GUI::new().report(
+            &Report::success(&format!("{} suggestions", suggestions.len()))
+                .index(self.get_param("index"))
+                .count(suggestions.len())
+                .with_data(data),
+        );
+
+        for suggestion in suggestions.iter() {
+            GUI::new().sub_title(&format!(
+                "{query} ({total} entries):",
+                query = suggestion.query,
+                total = suggestion.total
+            ));
+
+            for hit in suggestion.hits.iter() {
+                GUI::new().content(&format!("{}: {}", hit.key, hit.document));
+            }
+
+            GUI::new().nl();
+        }
+
+        Ok(())
+    }
+
+    // the options of whichever params the action has
+    fn search_options(&self) -> Result<SearchOptions> {
+        Ok(SearchOptions {
+            filter: match self.get_param_opt("filter") {
+                Some(filter) => Some(SearchFilter::parse(filter)?),
+                None => None,
+            },
+            sort: match self.get_param_opt("sort") {
+                Some(sort) => parse_sort(sort)?,
+                None => vec![],
+            },
+            limit: match self.get_param_opt("limit") {
+                Some(limit) => Some(limit.parse()?),
+                None => None,
+            },
+            offset: match self.get_param_opt("offset") {
+                Some(offset) => offset.parse()?,
+                None => 0,
+            },
+            after: self.get_param_opt("after").map(str::to_string),
+            fuzzy: match self.get_param_opt("fuzzy") {
+                Some(distance) => Some(Fuzzy {
+                    distance: distance.parse()?,
+                    metric: Metric::from_str(self.get_param("metric"))?,
+                }),
+                None => None,
+            },
+            prefix: self.get_param_bool("prefix"),
+        })
+    }
+
+    fn print_facets(facets: &[Facet]) {
+        GUI::new().sub_title("facets:");
+
+        for facet in facets.iter() {
+            let counts: Vec<String> = match &facet.counts {
+                FacetCounts::Values(values) => values
+                    .iter()
+                    .map(|(value, count)| match value {
+                        Value::String(value) => format!("{} ({})", value, count),
+                        value => format!("{} ({})", value, count),
+                    })
+                    .collect(),
+                FacetCounts::Ranges(ranges) => ranges
+                    .iter()
+                    .map(|range| format!("{}..{} ({})", range.from, range.to, range.count))
+                    .collect(),
+            };
+
+            GUI::new().content(&format!("{}: {}", facet.field, counts.join(", ")));
+        }
+
+        GUI::new().nl();
+    }
+
+    fn sync(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Search::rules(&Actions::Sync), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let engine = Settings::engine()?;
+        let count = engine.index(self.get_param("index"))?.sync()?;
+
+        GUI::new().report(
+            &Report::success(&format!(
+                "{count} entries synced for '{index}'",
+                count = count,
+                index = self.get_param("index")
+            ))
+            .index(self.get_param("index"))
+            .count(count),
+        );
+
+        Ok(())
+    }
+}
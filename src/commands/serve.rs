@@ -0,0 +1,772 @@
+use crate::tools::config::Settings;
+use crate::tools::gui::GUI;
+use crate::tools::report::Report;
+use crate::tools::validation::StringValidation::Ignore;
+use crate::tools::validation::{boolean, count, field, json, json_object, offset};
+use crate::traits::command::{derive_getters, ActionSpec, ParamRule, Params};
+use crate::traits::command::{Command, Runnable};
+use anyhow::{anyhow, Context, Ok, Result};
+use rusty_search::{
+    parse_sort, BulkOptions, Engine, Error, Filter, Fuzzy, Hit, IndexInfo, Metric, PatchFormat,
+    Schema, SearchOptions,
+};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::io::Read;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::str::FromStr;
+use strum_macros::{Display, EnumString};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+pub struct Serve {
+    pub params: Params,
+}
+
+#[derive(Display, EnumString, Debug)]
+enum Actions {
+    #[strum(ascii_case_insensitive)]
+    Start,
+    #[strum(ascii_case_insensitive)]
+    Help,
+}
+
+// request bodies over this many bytes are turned away with a 413
+const MAX_BODY: usize = 16 * 1024 * 1024;
+
+struct Reply {
+    status: u16,
+    body: Value,
+}
+
+impl Command for Serve {
+    derive_getters!();
+
+    fn actions(&self) -> Vec<ActionSpec> {
+        vec![ActionSpec {
+            name: "start",
+            rules: Serve::rules(&Actions::Start),
+            description: "Serve the api on {address}, defaults to the `address` setting",
+        }]
+    }
+
+    fn help(&self) -> Result<()> {
+        GUI::text()
+            .title("Serve Command")
+            .sub_title("actions:")
+            .nl()
+            .actions(&self.actions())
+            .nl()
+            .sub_title("endpoints:")
+            .nl()
+            .content("GET    /indexes                                   | List every index")
+            .content("GET    /indexes/{index}                           | Show the catalog entry and schema of {index}")
+            .content("PUT    /indexes/{index}                           | Create {index}, the body can be its schema")
+            .content("DELETE /indexes/{index}                           | Delete {index} and its data")
+            .content("POST   /indexes/{index}/init?key={key}            | Create {index} and populate it with the body's json array")
+            .content("GET    /indexes/{index}/search?q={query}          | Search {index}, `filter` narrows it down, `facets` counts values")
+            .content("GET    /indexes/{index}/suggest?q={query}         | Complete the last word of {query}, with a few entries each")
+            .content("GET    /indexes/{index}/documents/{key}?fields=   | Show the entry, `fields` like `name,brand.name`")
+            .content("POST   /indexes/{index}/mget?fields=              | Show the entries for the body's json array of keys")
+            .content("POST   /indexes/{index}/documents                 | Add the body as a new entry, keyed by its key field")
+            .content("POST   /indexes/{index}/documents/{key}           | Add the body as a new entry")
+            .content("PUT    /indexes/{index}/documents/{key}?create    | Update the entry, create it when `create` is set")
+            .content("PATCH  /indexes/{index}/documents/{key}           | Patch the entry with a merge patch or json patch body")
+            .content("DELETE /indexes/{index}/documents/{key}           | Remove the entry")
+            .content("POST   /indexes/{index}/remove-by-query?q={query} | Count the matches, remove them when `apply` is set")
+            .content("POST   /indexes/{index}/update-by-query?q={query} | Count the matches, merge the body into them when `apply` is set")
+            .nl()
+            .content("* PUT, PATCH and DELETE on an entry take `?if_version={version}` and answer 409 when the")
+            .content("  entry is at another version")
+            .content("* search takes `sort` like `price:asc,rating:desc` and returns 20 hits unless `limit` is set,")
+            .content("  the next page comes from `offset` or from passing the returned `next` as `after`")
+            .content("* `fuzzy={edits}` on search also matches similar words, counted by `metric=damerau|levenshtein`")
+            .content("* `prefix` on search matches words starting with the last term, suggest takes `count` and `limit`")
+            .content("* bodies over 16 MiB are answered with 413, failures of the database with 500")
+            .nl();
+
+        Ok(())
+    }
+}
+
+impl Runnable for Serve {
+    fn run(&mut self, action: &str, params: &[String]) -> Result<()> {
+        let action = Actions::from_str(action).unwrap_or(Actions::Help);
+
+        match action {
+            Actions::Start => self.start(params)?,
+            Actions::Help => self.help()?,
+        }
+
+        Ok(())
+    }
+}
+
+impl Serve {
+    pub fn new() -> Self {
+        Self {
+            params: HashMap::default(),
+        }
+    }
+
+    fn rules(action: &Actions) -> Vec<ParamRule<'static>> {
+        match action {
+            Actions::Start => vec![ParamRule {
+                key: "address",
+                validation: Ignore,
+                required: &false,
+                short: Some('a'),
+                ..Default::default()
+            }],
+            _ => vec![],
+        }
+    }
+
+    fn start(&mut self, params: &[String]) -> Result<()> {
+        self.assert_params(Serve::rules(&Actions::Start), params)?;
+
+        GUI::new().print_params(self as &dyn Command);
+
+        let address = match self.get_param_opt("address") {
+            Some(address) => address.to_string(),
+            None => Settings::get().address.to_string(),
+        };
+
+        // the host is only looked up here, the settings just check its shape
+        let addresses: Vec<SocketAddr> = address
+            .to_socket_addrs()
+            .with_context(|| format!("Can't resolve address '{}'", address))?
+            .collect();
+        let server = Server::http(addresses.as_slice()).map_err(|err| anyhow!(err))?;
+
+        GUI::new()
+            .report(
+                &Report::success(&format!("Listening on http://{}", address))
+                    .with_data(json!({ "address": address })),
+            )
+            .sub_title("requests:");
+
+        for mut request in server.incoming_requests() {
+            let reply = match Serve::route(&mut request) {
+                Result::Ok(reply) => reply,
+                Err(err) => Serve::failure(err),
+            };
+
+            let gui = GUI::new();
+
+            gui.content(&format!(
+                "{method} {url} -> {status}",
+                method = request.method(),
+                url = request.url(),
+                status = reply.status
+            ));
+            gui.record(&json!({
+                "method": request.method().to_string(),
+                "url": request.url(),
+                "status": reply.status
+            }));
+
+            let response = Response::from_string(reply.body.to_string())
+                .with_status_code(reply.status)
+                .with_header(Header::from_str("Content-Type: application/json").unwrap());
+
+            if let Err(err) = request.respond(response) {
+                GUI::new().report(&Report::error(&err.to_string()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Serve {
+    fn route(request: &mut Request) -> Result<Reply> {
+        let (path, query) = match request.url().split_once('?') {
+            Some((path, query)) => (path.to_string(), Serve::query_params(query)),
+            None => (request.url().to_string(), HashMap::new()),
+        };
+
+        let segments: Vec<String> = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(Serve::decode)
+            .collect();
+        let segments: Vec<&str> = segments.iter().map(|s| s.as_str()).collect();
+
+        // the patch format comes from the content type, or the shape of the body
+        let format = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Content-Type"))
+            .and_then(|header| match header.value.as_str() {
+                "application/merge-patch+json" => Some(PatchFormat::Merge),
+                "application/json-patch+json" => Some(PatchFormat::Json),
+                _ => None,
+            });
+
+        // a body is never read past the limit, even without a content length
+        if request.body_length().unwrap_or_default() > MAX_BODY {
+            return Ok(Serve::too_large());
+        }
+
+        let mut body = Vec::new();
+        request
+            .as_reader()
+            .take(MAX_BODY as u64 + 1)
+            .read_to_end(&mut body)?;
+
+        if body.len() > MAX_BODY {
+            return Ok(Serve::too_large());
+        }
+
+        let body = String::from_utf8(body).context("Invalid request body, expected utf-8")?;
+
+        let engine = Settings::engine()?;
+
+        match (request.method(), segments.as_slice()) {
+            (Method::Get, ["indexes"]) => Serve::list_indexes(&engine),
+            (Method::Get, ["indexes", index]) => Serve::index_info(&engine, index),
+            (Method::Put, ["indexes", index]) => Serve::create_index(&engine, index, &body),
+            (Method::Delete, ["indexes", index]) => Serve::delete_index(&engine, index),
+            (Method::Post, ["indexes", index, "init"]) => {
+                Serve::init_index(&engine, index, query.get("key"), &body)
+            }
+            (Method::Get, ["indexes", index, "search"]) => Serve::search(&engine, index, &query),
+            (Method::Get, ["indexes", index, "suggest"]) => Serve::suggest(&engine, index, &query),
+            (Method::Get, ["indexes", index, "documents", key]) => {
+                Serve::get_document(&engine, index, key, query.get("fields"))
+            }
+            (Method::Post, ["indexes", index, "mget"]) => {
+                Serve::get_documents(&engine, index, &body, query.get("fields"))
+            }
+            (Method::Post, ["indexes", index, "documents"]) => {
+                Serve::add_document(&engine, index, None, &body)
+            }
+            (Method::Post, ["indexes", index, "documents", key]) => {
+                Serve::add_document(&engine, index, Some(key), &body)
+            }
+            (Method::Put, ["indexes", index, "documents", key]) => {
+                let if_version = Serve::if_version(&query)?;
+                let create = Serve::flag(&query, "create")?;
+                Serve::update_document(&engine, index, key, &body, create, if_version)
+            }
+            (Method::Patch, ["indexes", index, "documents", key]) => {
+                let if_version = Serve::if_version(&query)?;
+                Serve::patch_document(&engine, index, key, &body, format, if_version)
+            }
+            (Method::Post, ["indexes", index, "remove-by-query"]) => {
+                let apply = Serve::flag(&query, "apply")?;
+                Serve::by_query(&engine, index, &query, None, apply)
+            }
+            (Method::Post, ["indexes", index, "update-by-query"]) => {
+                json_object(&body, "patch")?;
+                let patch = serde_json::from_str(&body)?;
+                let apply = Serve::flag(&query, "apply")?;
+                Serve::by_query(&engine, index, &query, Some(&patch), apply)
+            }
+            (Method::Delete, ["indexes", index, "documents", key]) => {
+                Serve::remove_document(&engine, index, key, Serve::if_version(&query)?)
+            }
+            (method, _) => Ok(Serve::error(
+                404,
+                &format!("No endpoint for {} {}", method, path),
+            )),
+        }
+    }
+
+    fn list_indexes(engine: &Engine) -> Result<Reply> {
+        let indexes: Vec<Value> = engine.indexes()?.iter().map(Serve::info).collect();
+
+        Ok(Serve::success(200, json!({ "indexes": indexes })))
+    }
+
+    fn index_info(engine: &Engine, index: &str) -> Result<Reply> {
+        let index = engine.index(index)?;
+        let mut info = Serve::info(&index.info()?);
+        info["schema"] = index.schema()?.to_json();
+
+        Ok(Serve::success(200, info))
+    }
+
+    fn info(info: &IndexInfo) -> Value {
+        json!({
+            "name": info.name,
+            "key_field": info.key_field,
+            "documents": info.documents,
+            "size": info.size,
+            "created": info.created,
+            "updated": info.updated,
+        })
+    }
+
+    // an optional body is the schema of the index
+    fn create_index(engine: &Engine, index: &str, body: &str) -> Result<Reply> {
+        let schema = match body.trim().is_empty() {
+            true => None,
+            false => {
+                json(body, "schema")?;
+                Some(Schema::from_json(&serde_json::from_str(body)?)?)
+            }
+        };
+
+        let created = engine.create_index(index)?;
+
+        if let Some(schema) = &schema {
+            created.set_schema(schema)?;
+        }
+
+        Ok(Serve::success(
+            201,
+            json!({ "index": index, "schema": created.schema()?.to_json() }),
+        ))
+    }
+
+    fn delete_index(engine: &Engine, index: &str) -> Result<Reply> {
+        engine.delete_index(index)?;
+
+        Ok(Serve::success(200, json!({ "index": index })))
+    }
+
+    fn init_index(engine: &Engine, index: &str, key: Option<&String>, body: &str) -> Result<Reply> {
+        let key = key.ok_or_else(|| anyhow!("No value entered for the param: key"))?;
+        field(key)?;
+        json(body, "data")?;
+
+        let documents: Vec<Value> = serde_json::from_str(body)?;
+        let summary = engine.init_index(index, key, &documents, BulkOptions::default())?;
+
+        Ok(Serve::success(
+            201,
+            json!({ "index": index, "count": summary.inserted }),
+        ))
+    }
+
+    fn search(engine: &Engine, index: &str, query: &HashMap<String, String>) -> Result<Reply> {
+        let (text, mut options) = Serve::search_params(query)?;
+
+        // pages are 20 hits long unless `limit` says otherwise
+        if let Some(sort) = query.get("sort") {
+            options.sort = parse_sort(sort)?;
+        }
+        options.limit = Some(Serve::count_param(query, "limit", 20)?);
+        if let Some(skip) = query.get("offset") {
+            offset(skip, "offset")?;
+            options.offset = skip.parse()?;
+        }
+        options.after = query.get("after").cloned();
+        options.prefix = Serve::flag(query, "prefix")?;
+        options.fuzzy = match query.get("fuzzy").map(|distance| distance.as_str()) {
+            Some(distance) => Some(Fuzzy {
+                distance: match distance {
+                    "" => Fuzzy::default().distance,
+                    distance => {
+                        count(distance, "fuzzy")?;
+                        distance.parse()?
+                    }
+                },
+                metric: match query.get("metric") {
+                    Some(metric) => Metric::from_str(metric).map_err(|_| {
+                        anyhow!("Invalid value for metric, expected damerau|levenshtein")
+                    })?,
+                    None => Fuzzy::default().metric,
+                },
+            }),
+            None => None,
+        };
+
+        let index = engine.index(index)?;
+        let results = index.search(text, &options)?;
+        let hits: Vec<Value> = results.hits.iter().map(Hit::to_json).collect();
+
+        let mut body = json!({
+            "index": index.name(),
+            "count": hits.len(),
+            "total": results.total,
+            "next": results.next,
+            "hits": hits,
+        });
+
+        if let Some(fields) = Serve::fields(query.get("facets"))? {
+            let fields: Vec<String> = fields.iter().map(|field| field.to_string()).collect();
+
+            body["facets"] = index
+                .facets(text, &options, &fields)?
+                .iter()
+                .map(|facet| (facet.field.to_string(), facet.to_json()))
+                .collect::<Map<String, Value>>()
+                .into();
+        }
+
+        Ok(Serve::success(200, body))
+    }
+
+    fn suggest(engine: &Engine, index: &str, query: &HashMap<String, String>) -> Result<Reply> {
+        let (text, mut options) = Serve::search_params(query)?;
+        options.limit = Some(Serve::count_param(query, "limit", 3)?);
+
+        let index = engine.index(index)?;
+        let suggestions: Vec<Value> = index
+            .suggest(text, &options, Serve::count_param(query, "count", 5)?)?
+            .iter()
+            .map(|suggestion| {
+                json!({
+                    "query": suggestion.query,
+                    "total": suggestion.total,
+                    "hits": suggestion.hits.iter().map(Hit::to_json).collect::<Vec<Value>>(),
+                })
+            })
+            .collect();
+
+        Ok(Serve::success(
+            200,
+            json!({ "index": index.name(), "count": suggestions.len(), "suggestions": suggestions }),
+        ))
+    }
+
+    fn get_document(
+        engine: &Engine,
+        index: &str,
+        key: &str,
+        fields: Option<&String>,
+    ) -> Result<Reply> {
+        let fields = Serve::fields(fields)?;
+        let index = engine.index(index)?;
+
+        match index.get_document(key)? {
+            Some(mut hit) => {
+                if let Some(fields) = &fields {
+                    hit.project(fields);
+                }
+
+                let mut body = hit.to_json();
+                body["index"] = json!(index.name());
+
+                Ok(Serve::success(200, body))
+            }
+            None => Ok(Serve::missing_entry(index.name(), key)),
+        }
+    }
+
+    // missing keys come back with a null `data` instead of failing the request
+    fn get_documents(
+        engine: &Engine,
+        index: &str,
+        body: &str,
+        fields: Option<&String>,
+    ) -> Result<Reply> {
+        json(body, "keys")?;
+
+        let fields = Serve::fields(fields)?;
+        let keys: Vec<String> = serde_json::from_str(body)
+            .map_err(|_| anyhow!("Invalid value for keys, expected a json array of strings"))?;
+        let index = engine.index(index)?;
+        let mut hits = Vec::new();
+
+        for (key, hit) in keys.iter().zip(index.get_documents(&keys)?) {
+            hits.push(match hit {
+                Some(mut hit) => {
+                    if let Some(fields) = &fields {
+                        hit.project(fields);
+                    }
+
+                    hit.to_json()
+                }
+                None => json!({ "key": key, "data": null }),
+            });
+        }
+
+        Ok(Serve::success(
+            200,
+            json!({ "index": index.name(), "count": hits.len(), "hits": hits }),
+        ))
+    }
+
+    // without a key in the path it's taken from the key field of the body
+    fn add_document(engine: &Engine, index: &str, key: Option<&str>, body: &str) -> Result<Reply> {
+        json(body, "data")?;
+
+        let index = engine.index(index)?;
+        let document = serde_json::from_str(body)?;
+        let key = match key {
+            Some(key) => key.to_string(),
+            None => index.derive_key(&document)?,
+        };
+        let warning = index.check_key(&key, &document)?;
+
+        index.add_document(&key, &document)?;
+
+        Ok(Serve::success(
+            201,
+            json!({ "index": index.name(), "key": key, "warning": warning }),
+        ))
+    }
+
+    fn update_document(
+        engine: &Engine,
+        index: &str,
+        key: &str,
+        body: &str,
+        create: bool,
+        if_version: Option<u64>,
+    ) -> Result<Reply> {
+        json(body, "data")?;
+
+        let index = engine.index(index)?;
+        let document = serde_json::from_str(body)?;
+        let warning = index.check_key(key, &document)?;
+
+        if create && !index.has_document(key)? {
+            index.add_document(key, &document)?;
+
+            return Ok(Serve::success(
+                201,
+                json!({ "index": index.name(), "key": key, "version": 1, "warning": warning }),
+            ));
+        }
+
+        match index.update_document(key, &document, if_version)? {
+            Some(version) => Ok(Serve::success(
+                200,
+                json!({ "index": index.name(), "key": key, "version": version, "warning": warning }),
+            )),
+            None => Ok(Serve::missing_entry(index.name(), key)),
+        }
+    }
+
+    fn patch_document(
+        engine: &Engine,
+        index: &str,
+        key: &str,
+        body: &str,
+        format: Option<PatchFormat>,
+        if_version: Option<u64>,
+    ) -> Result<Reply> {
+        json(body, "patch")?;
+
+        let index = engine.index(index)?;
+        let patch = serde_json::from_str(body)?;
+        let format = format.unwrap_or_else(|| PatchFormat::detect(&patch));
+
+        match index.patch_document(key, &patch, format, if_version)? {
+            Some((hit, warning)) => Ok(Serve::success(
+                200,
+                json!({
+                    "index": index.name(),
+                    "key": key,
+                    "version": hit.version,
+                    "modified": hit.modified,
+                    "data": hit.document,
+                    "warning": warning,
+                }),
+            )),
+            None => Ok(Serve::missing_entry(index.name(), key)),
+        }
+    }
+
+    // removes the matches, or merges the patch into them, only when `apply`
+    // is set. otherwise it's a dry run that lists the matching keys
+    fn by_query(
+        engine: &Engine,
+        index: &str,
+        query: &HashMap<String, String>,
+        patch: Option<&Value>,
+        apply: bool,
+    ) -> Result<Reply> {
+        let (text, options) = Serve::search_params(query)?;
+        let index = engine.index(index)?;
+
+        if !apply {
+            let keys = index.search_keys(text, &options)?;
+
+            return Ok(Serve::success(
+                200,
+                json!({ "index": index.name(), "count": keys.len(), "dry_run": true, "keys": keys }),
+            ));
+        }
+
+        let count = match patch {
+            Some(patch) => index.update_by_query(text, &options, patch)?,
+            None => index.remove_by_query(text, &options)?,
+        };
+
+        Ok(Serve::success(
+            200,
+            json!({ "index": index.name(), "count": count, "dry_run": false }),
+        ))
+    }
+
+    fn remove_document(
+        engine: &Engine,
+        index: &str,
+        key: &str,
+        if_version: Option<u64>,
+    ) -> Result<Reply> {
+        let index = engine.index(index)?;
+
+        match index.remove_document(key, if_version)? {
+            true => Ok(Serve::success(
+                200,
+                json!({ "index": index.name(), "key": key }),
+            )),
+            false => Ok(Serve::missing_entry(index.name(), key)),
+        }
+    }
+}
+
+impl Serve {
+    fn success(status: u16, mut body: Value) -> Reply {
+        body["status"] = json!("success");
+
+        Reply { status, body }
+    }
+
+    fn error(status: u16, message: &str) -> Reply {
+        Reply {
+            status,
+            body: json!({ "status": "error", "error": message }),
+        }
+    }
+
+    // library errors that map onto a more specific status than 400, failures
+    // of the database or the disk are the server's fault rather than the request's
+    fn failure(err: anyhow::Error) -> Reply {
+        if err.is::<rusqlite::Error>() || err.is::<std::io::Error>() {
+            return Serve::error(500, &err.to_string());
+        }
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::Sqlite(_) | Error::Io(_)) => Serve::error(500, &err.to_string()),
+            Some(Error::IndexNotFound(_)) => Serve::error(404, &err.to_string()),
+            Some(
+                Error::EntryExists { .. }
+                | Error::KeyMismatch { .. }
+                | Error::VersionConflict { .. },
+            ) => Serve::error(409, &err.to_string()),
+            Some(Error::InvalidDocument { .. } | Error::InvalidPatch(_)) => {
+                Serve::error(422, &err.to_string())
+            }
+            _ => Serve::error(400, &err.to_string()),
+        }
+    }
+
+    // `q` can be left out when there's a `filter`
+    fn search_params(query: &HashMap<String, String>) -> Result<(&str, SearchOptions)> {
+        let text = query.get("q").map(|q| q.as_str()).unwrap_or_default();
+        let filter = match query.get("filter") {
+            Some(filter) => Some(Filter::parse(filter)?),
+            None => None,
+        };
+
+        Ok((
+            text,
+            SearchOptions {
+                filter,
+                ..Default::default()
+            },
+        ))
+    }
+
+    fn count_param(query: &HashMap<String, String>, name: &str, default: usize) -> Result<usize> {
+        match query.get(name) {
+            Some(value) => {
+                count(value, name)?;
+                Ok(value.parse()?)
+            }
+            None => Ok(default),
+        }
+    }
+
+    // a bare `?name` counts as true, like a cli flag
+    fn flag(query: &HashMap<String, String>, name: &str) -> Result<bool> {
+        match query.get(name).map(|value| value.as_str()) {
+            Some("") => Ok(true),
+            Some(value) => {
+                boolean(value, name)?;
+                Ok(value == "true")
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn fields(fields: Option<&String>) -> Result<Option<Vec<&str>>> {
+        match fields {
+            Some(fields) => {
+                let fields: Vec<&str> = fields.split(',').collect();
+                fields.iter().try_for_each(|name| field(name))?;
+
+                Ok(Some(fields))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn if_version(query: &HashMap<String, String>) -> Result<Option<u64>> {
+        match query.get("if_version") {
+            Some(version) => {
+                count(version, "if_version")?;
+                Ok(Some(version.parse()?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn too_large() -> Reply {
+        Serve::error(
+            413,
+            &format!(
+                "Request body too large, expected at most {} bytes",
+                MAX_BODY
+            ),
+        )
+    }
+
+    fn missing_entry(index: &str, key: &str) -> Reply {
+        Serve::error(404, &format!("No matching entry for {}->{}", index, key))
+    }
+
+    fn query_params(query: &str) -> HashMap<String, String> {
+        query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((name, value)) => (Serve::decode_query(name), Serve::decode_query(value)),
+                None => (Serve::decode_query(pair), String::new()),
+            })
+            .collect()
+    }
+
+    // `+` is only a space in query strings, in a path it's a `+`
+    fn decode_query(component: &str) -> String {
+        Serve::decode(&component.replace('+', " "))
+    }
+
+    // percent decodes a url component
+    fn decode(component: &str) -> String {
+        let bytes = component.as_bytes();
+        let mut decoded = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+
+        while i < bytes.len() {
+            match bytes[i] {
+                // from_str_radix takes a sign, so `%+5` has to be ruled out first
+                b'%' if i + 2 < bytes.len()
+                    && bytes[i + 1].is_ascii_hexdigit()
+                    && bytes[i + 2].is_ascii_hexdigit() =>
+                {
+                    let hex = String::from_utf8_lossy(&bytes[i + 1..i + 3]);
+
+                    match u8::from_str_radix(&hex, 16) {
+                        Result::Ok(byte) => {
+                            decoded.push(byte);
+                            i += 2;
+                        }
+                        Err(_) => decoded.push(b'%'),
+                    }
+                }
+                byte => decoded.push(byte),
+            }
+            i += 1;
+        }
+
+        String::from_utf8_lossy(&decoded).to_string()
+    }
+}
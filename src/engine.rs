@@ -0,0 +1,188 @@
+use crate::bulk::{Batch, BulkOptions, BulkSummary};
+use crate::catalog::{self, IndexInfo};
+use crate::error::{Error, Result};
+use crate::import::document_key;
+use crate::index::Index;
+use crate::{rank, schema, search};
+use rusqlite::Connection;
+use serde_json::Value;
+use std::path::Path;
+
+pub struct Engine {
+    conn: Connection,
+}
+
+impl Engine {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Engine::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Engine::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        catalog::create_table(&conn)?;
+
+        let engine = Self { conn };
+
+        // indexes made before entries had versions
+        for name in engine.index_names()? {
+            let has_version: bool = engine.conn.query_row(
+                "SELECT count(*) FROM pragma_table_info(?1) WHERE `name` = 'version'",
+                [&name],
+                |row| row.get(0),
+            )?;
+
+            if !has_version {
+                engine.conn.execute_batch(&format!(
+                    "ALTER TABLE `{table}` ADD COLUMN `version` INTEGER NOT NULL DEFAULT 1;
+                    ALTER TABLE `{table}` ADD COLUMN `modified` TEXT;
+                    ",
+                    table = name
+                ))?;
+            }
+
+            // and before the term dictionary
+            if !does_table_exist(&engine.conn, &search::terms_table_name(&name))? {
+                search::create_terms_table(&engine.conn, &name)?;
+            }
+
+            // and before search rows were numbered from their entry's rowid
+            if search::is_outdated(&engine.conn, &name)? {
+                search::rebuild_table(&engine.conn, &name)?;
+            }
+        }
+
+        Ok(engine)
+    }
+
+    pub fn has_index(&self, name: &str) -> Result<bool> {
+        validate_index(name)?;
+
+        does_table_exist(&self.conn, name)
+    }
+
+    // every table with a search table next to it, index names can't contain
+    // the `_` of the tables that belong to them
+    pub fn index_names(&self) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT `name` FROM `sqlite_master` AS `index`
+            WHERE `type` = 'table' AND EXISTS (
+                SELECT 1 FROM `sqlite_master`
+                WHERE `type` = 'table' AND `name` = `index`.`name` || '_search')
+            ORDER BY `name`",
+        )?;
+
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        let mut names = Vec::new();
+
+        for row in rows {
+            names.push(row?);
+        }
+
+        Ok(names)
+    }
+
+    // the catalog entry of every index, in name order
+    pub fn indexes(&self) -> Result<Vec<IndexInfo>> {
+        let mut indexes = Vec::new();
+
+        for name in self.index_names()? {
+            indexes.push(self.index(&name)?.info()?);
+        }
+
+        Ok(indexes)
+    }
+
+    pub fn index(&self, name: &str) -> Result<Index<'_>> {
+        match self.has_index(name)? {
+            true => Ok(Index::new(&self.conn, name)),
+            false => Err(Error::IndexNotFound(name.to_string())),
+        }
+    }
+
+    // replaces any existing index of the same name, ranking settings and the
+    // schema are kept
+    pub fn create_index(&self, name: &str) -> Result<Index<'_>> {
+        validate_index(name)?;
+
+        self.conn.execute_batch(&format!(
+            "DROP TABLE if exists `{table}`;
+            CREATE TABLE `{table}` (
+                `key` TEXT PRIMARY KEY,
+                `data` TEXT,
+                `version` INTEGER NOT NULL DEFAULT 1,
+                `modified` TEXT);
+            ",
+            table = name
+        ))?;
+
+        search::create_table(&self.conn, name)?;
+        catalog::register(&self.conn, name)?;
+
+        Ok(Index::new(&self.conn, name))
+    }
+
+    // creates the index and adds every document, using the value of
+    // `key_field` in each document as its key. the index is only replaced
+    // once its documents are committed, so an atomic load that fails leaves
+    // the previous index as it was
+    pub fn init_index(
+        &self,
+        name: &str,
+        key_field: &str,
+        documents: &[Value],
+        options: BulkOptions,
+    ) -> Result<BulkSummary> {
+        let mut batch = Batch::begin(&self.conn, options)?;
+        let index = self.create_index(name)?;
+        let schema = index.schema()?;
+        catalog::set_key_field(&self.conn, name, key_field)?;
+
+        for document in documents {
+            batch.record(|| {
+                let key = document_key(name, document, key_field)?;
+                index.insert_document(&key, document, &schema)?;
+
+                Ok(true)
+            })?;
+        }
+
+        batch.commit()
+    }
+
+    pub fn delete_index(&self, name: &str) -> Result<()> {
+        if !self.has_index(name)? {
+            return Err(Error::IndexNotFound(name.to_string()));
+        }
+
+        self.conn
+            .execute(&format!("DROP TABLE `{table}`", table = name), [])?;
+
+        search::drop_table(&self.conn, name)?;
+        rank::drop_table(&self.conn, name)?;
+        schema::drop_table(&self.conn, name)?;
+        catalog::remove(&self.conn, name)?;
+
+        Ok(())
+    }
+}
+
+pub fn does_table_exist(conn: &Connection, table: &str) -> Result<bool> {
+    let result: bool = conn.query_row(
+        "SELECT count(*) FROM `sqlite_master` WHERE `type` = 'table' AND `name` = ?1",
+        [table],
+        |row| row.get(0),
+    )?;
+
+    Ok(result)
+}
+
+// index names are used as table names, so they're limited to letters
+pub fn validate_index(name: &str) -> Result<()> {
+    match !name.is_empty() && name.chars().all(|c| c.is_alphabetic()) {
+        true => Ok(()),
+        false => Err(Error::InvalidIndex(name.to_string())),
+    }
+}
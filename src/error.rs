@@ -0,0 +1,160 @@
+use std::fmt::{Display, Formatter};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidIndex(String),
+    InvalidField(String),
+    InvalidQuery(String),
+    InvalidFilter {
+        // the column of the offending token, from 1
+        position: usize,
+        reason: String,
+    },
+    InvalidSchema(String),
+    InvalidPatch(String),
+    InvalidRank(String),
+    InvalidDocument {
+        index: String,
+        errors: Vec<String>,
+    },
+    IndexNotFound(String),
+    EntryExists {
+        index: String,
+        key: String,
+    },
+    MissingKey {
+        index: String,
+        field: String,
+    },
+    NoKeyField(String),
+    KeyMismatch {
+        index: String,
+        key: String,
+        field: String,
+        value: String,
+    },
+    VersionConflict {
+        index: String,
+        key: String,
+        expected: u64,
+        version: u64,
+    },
+    Record {
+        record: usize,
+        source: Box<Error>,
+    },
+    Sqlite(rusqlite::Error),
+    Json(serde_json::Error),
+    Csv(csv::Error),
+    Io(std::io::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::InvalidIndex(index) => write!(
+                f,
+                "Invalid value for index '{}', expected alphabetic string",
+                index
+            ),
+            Error::InvalidField(field) => write!(
+                f,
+                "Invalid value for field '{}', expected a field name like `brand` or `brand.name`",
+                field
+            ),
+            Error::InvalidQuery(reason) => write!(f, "Invalid search query, {}", reason),
+            Error::InvalidFilter { position, reason } => {
+                write!(f, "Invalid filter at column {}, {}", position, reason)
+            }
+            Error::InvalidSchema(reason) => write!(f, "Invalid schema, {}", reason),
+            Error::InvalidPatch(reason) => write!(f, "Invalid patch, {}", reason),
+            Error::InvalidRank(reason) => write!(f, "Invalid ranking rule, {}", reason),
+            Error::InvalidDocument { index, errors } => write!(
+                f,
+                "Entry for '{}' doesn't match its schema, {}",
+                index,
+                errors.join("; ")
+            ),
+            Error::IndexNotFound(index) => write!(f, "No index called '{}'", index),
+            Error::EntryExists { index, key } => {
+                write!(f, "Entry already exists for {}->{}", index, key)
+            }
+            Error::MissingKey { index, field } => write!(
+                f,
+                "Entry for '{}' has no string or number value for the key: {}",
+                index, field
+            ),
+            Error::NoKeyField(index) => write!(
+                f,
+                "No key field is set for '{}', keys can't be taken from its entries",
+                index
+            ),
+            Error::KeyMismatch {
+                index,
+                key,
+                field,
+                value,
+            } => write!(
+                f,
+                "Entry for {}->{} has a different key in `{}`: '{}'",
+                index, key, field, value
+            ),
+            Error::VersionConflict {
+                index,
+                key,
+                expected,
+                version,
+            } => write!(
+                f,
+                "Entry for {}->{} is at version {}, not {}",
+                index, key, version, expected
+            ),
+            Error::Record { record, source } => {
+                write!(f, "Failed at record {}, {}", record, source)
+            }
+            Error::Sqlite(err) => write!(f, "{}", err),
+            Error::Json(err) => write!(f, "{}", err),
+            Error::Csv(err) => write!(f, "{}", err),
+            Error::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Record { source, .. } => Some(source.as_ref()),
+            Error::Sqlite(err) => Some(err),
+            Error::Json(err) => Some(err),
+            Error::Csv(err) => Some(err),
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Error::Sqlite(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(err: csv::Error) -> Self {
+        Error::Csv(err)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
@@ -0,0 +1,120 @@
+use crate::error::Result;
+use crate::import::Format;
+use rusqlite::Connection;
+use serde_json::Value;
+use std::io::Write;
+
+// writes documents one at a time, csv needs its columns up front so the
+// header can be written before the first row
+pub struct Exporter<W: Write> {
+    writer: Writer<W>,
+    columns: Vec<String>,
+    count: usize,
+}
+
+enum Writer<W: Write> {
+    Json(W),
+    Ndjson(W),
+    Csv(Box<csv::Writer<W>>),
+}
+
+impl<W: Write> Exporter<W> {
+    pub fn new(writer: W, format: Format, columns: Vec<String>) -> Result<Self> {
+        let writer = match format {
+            Format::Json => Writer::Json(writer),
+            Format::Ndjson => Writer::Ndjson(writer),
+            Format::Csv => {
+                let mut writer = csv::Writer::from_writer(writer);
+                writer.write_record(&columns)?;
+                Writer::Csv(Box::new(writer))
+            }
+        };
+
+        Ok(Self {
+            writer,
+            columns,
+            count: 0,
+        })
+    }
+
+    pub fn write(&mut self, document: &Value) -> Result<()> {
+        match &mut self.writer {
+            Writer::Json(writer) => {
+                let separator = match self.count {
+                    0 => "[\n",
+                    _ => ",\n",
+                };
+                write!(writer, "{}{}", separator, document)?;
+            }
+            Writer::Ndjson(writer) => writeln!(writer, "{}", document)?,
+            Writer::Csv(writer) => {
+                writer.write_record(self.columns.iter().map(|column| cell(document.get(column))))?
+            }
+        }
+
+        self.count += 1;
+
+        Ok(())
+    }
+
+    // returns the number of documents written
+    pub fn finish(self) -> Result<usize> {
+        match self.writer {
+            Writer::Json(mut writer) => {
+                match self.count {
+                    0 => writeln!(writer, "[]")?,
+                    _ => writeln!(writer, "\n]")?,
+                }
+                writer.flush()?;
+            }
+            Writer::Ndjson(mut writer) => writer.flush()?,
+            Writer::Csv(mut writer) => writer.flush()?,
+        }
+
+        Ok(self.count)
+    }
+}
+
+// the top level fields of every document in the index, in order of first use
+pub fn fields(conn: &Connection, index: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT `field`.`key` FROM `{table}`, json_each(`{table}`.`data`) AS `field`
+        GROUP BY `field`.`key`
+        ORDER BY MIN(`{table}`.`rowid`), MIN(`field`.`id`)",
+        table = index
+    ))?;
+
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    let mut fields = Vec::new();
+
+    for row in rows {
+        fields.push(row?);
+    }
+
+    Ok(fields)
+}
+
+pub fn document_columns<'a, I: Iterator<Item = &'a Value>>(documents: I) -> Vec<String> {
+    let mut columns: Vec<String> = Vec::new();
+
+    for document in documents {
+        if let Value::Object(map) = document {
+            for name in map.keys() {
+                if !columns.contains(name) {
+                    columns.push(name.to_string());
+                }
+            }
+        }
+    }
+
+    columns
+}
+
+// nested values are kept as json text so they survive the round trip
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => text.to_string(),
+        Some(value) => value.to_string(),
+    }
+}
@@ -0,0 +1,208 @@
+use serde_json::{json, Map, Value};
+
+// numeric facets are split into about this many ranges
+const RANGES: f64 = 5.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FacetRange {
+    // `from` is included and `to` isn't
+    pub from: f64,
+    pub to: f64,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FacetCounts {
+    // each value with the number of entries that have it, most common first
+    Values(Vec<(Value, usize)>),
+    // numeric fields are counted in ranges of the same width, lowest first
+    Ranges(Vec<FacetRange>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Facet {
+    pub field: String,
+    pub counts: FacetCounts,
+}
+
+impl Facet {
+    // values become a `{"value": count}` map and ranges a list of
+    // `{"from", "to", "count"}` objects
+    pub fn to_json(&self) -> Value {
+        match &self.counts {
+            FacetCounts::Values(values) => {
+                let mut map = Map::new();
+
+                for (value, count) in values.iter() {
+                    map.insert(label(value), json!(count));
+                }
+
+                Value::Object(map)
+            }
+            FacetCounts::Ranges(ranges) => ranges
+                .iter()
+                .map(|range| json!({"from": range.from, "to": range.to, "count": range.count}))
+                .collect(),
+        }
+    }
+}
+
+// strings without their quotes, anything else as json
+fn label(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.to_string(),
+        value => value.to_string(),
+    }
+}
+
+// puts the counted values into ranges with a round width like 5, 20 or 0.5.
+// an entry with several numbers in one range is counted for each of them
+pub(crate) fn ranges(values: &[(Value, usize)]) -> Vec<FacetRange> {
+    let numbers: Vec<(f64, usize)> = values
+        .iter()
+        .filter_map(|(value, count)| value.as_f64().map(|number| (number, *count)))
+        .collect();
+
+    if numbers.is_empty() {
+        return vec![];
+    }
+
+    let min = numbers
+        .iter()
+        .map(|(n, _)| *n)
+        .fold(f64::INFINITY, f64::min);
+    let max = numbers
+        .iter()
+        .map(|(n, _)| *n)
+        .fold(f64::NEG_INFINITY, f64::max);
+
+    let width = round_width((max - min) / RANGES);
+    // edges are rounded to the decimals of the width, so they read as 0.3
+    // instead of 0.30000000000000004 and a value on an edge starts its range
+    let scale = 10f64.powi((-width.log10().floor()).max(0.0) as i32);
+    let edge = |i: f64| (i * width * scale).round() / scale;
+
+    let mut first = (min / width).floor();
+
+    if edge(first + 1.0) <= min {
+        first += 1.0;
+    }
+
+    let mut ranges: Vec<FacetRange> = Vec::new();
+
+    loop {
+        let i = first + ranges.len() as f64;
+        let range = FacetRange {
+            from: edge(i),
+            to: edge(i + 1.0),
+            count: 0,
+        };
+        let last = range.to > max;
+
+        ranges.push(range);
+
+        if last {
+            break;
+        }
+    }
+
+    for (number, count) in numbers {
+        let i = ranges
+            .partition_point(|range| range.from <= number)
+            .saturating_sub(1);
+        ranges[i].count += count;
+    }
+
+    ranges
+}
+
+// the smallest of 1, 2 or 5 times a power of ten that is at least `width`
+fn round_width(width: f64) -> f64 {
+    if width <= 0.0 || !width.is_finite() {
+        return 1.0;
+    }
+
+    let power = 10f64.powf(width.log10().floor());
+
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|step| step * power)
+        .find(|step| *step >= width)
+        .unwrap_or(10.0 * power)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // each range as `from..to count`
+    fn counted(values: &[f64]) -> Vec<String> {
+        let values: Vec<(Value, usize)> = values.iter().map(|value| (json!(value), 1)).collect();
+
+        ranges(&values)
+            .iter()
+            .map(|range| format!("{}..{} {}", range.from, range.to, range.count))
+            .collect()
+    }
+
+    #[test]
+    fn values_on_an_edge_start_the_next_range() {
+        assert_eq!(
+            counted(&[0.0, 10.0, 20.0, 50.0]),
+            ["0..10 1", "10..20 1", "20..30 1", "30..40 0", "40..50 0", "50..60 1"]
+        );
+    }
+
+    #[test]
+    fn fractional_edges_are_rounded() {
+        assert_eq!(
+            counted(&[0.1, 0.2, 0.3]),
+            [
+                "0.1..0.15 1",
+                "0.15..0.2 0",
+                "0.2..0.25 1",
+                "0.25..0.3 0",
+                "0.3..0.35 1"
+            ]
+        );
+    }
+
+    #[test]
+    fn a_single_value_gets_one_range() {
+        assert_eq!(counted(&[7.0, 7.0]), ["7..8 2"]);
+        assert_eq!(counted(&[0.5]), ["0..1 1"]);
+    }
+
+    #[test]
+    fn negative_numbers_round_down() {
+        assert_eq!(
+            counted(&[-15.0, -3.0, 4.0]),
+            ["-15..-10 1", "-10..-5 0", "-5..0 1", "0..5 1"]
+        );
+        assert_eq!(
+            counted(&[-2.5, -0.5]),
+            [
+                "-2.5..-2 1",
+                "-2..-1.5 0",
+                "-1.5..-1 0",
+                "-1..-0.5 0",
+                "-0.5..0 1"
+            ]
+        );
+    }
+
+    #[test]
+    fn values_that_arent_numbers_are_left_out() {
+        let values = vec![(json!("cheap"), 3), (json!(5), 2)];
+
+        assert_eq!(
+            ranges(&values),
+            [FacetRange {
+                from: 5.0,
+                to: 6.0,
+                count: 2
+            }]
+        );
+        assert!(ranges(&[(json!("cheap"), 3)]).is_empty());
+    }
+}
@@ -0,0 +1,723 @@
+use crate::error::{Error, Result};
+use crate::query::is_field_name;
+use crate::schema::{FieldType, Schema};
+use crate::search::json_path;
+use rusqlite::types::Value as SqlValue;
+use serde_json::Value;
+use strum_macros::Display;
+
+#[derive(Display, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    #[strum(serialize = "=")]
+    Eq,
+    #[strum(serialize = "!=")]
+    Ne,
+    #[strum(serialize = "<")]
+    Lt,
+    #[strum(serialize = "<=")]
+    Le,
+    #[strum(serialize = ">")]
+    Gt,
+    #[strum(serialize = ">=")]
+    Ge,
+}
+
+// a field named in a filter, with the column it starts at for errors
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub position: usize,
+}
+
+// a parsed filter like `price < 50 AND brand IN ("nike", "adidas")`. a
+// field holding an array matches when any of its items does
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Compare { field: Field, op: Op, value: Value },
+    In { field: Field, values: Vec<Value> },
+    // the field is there and isn't null
+    Exists { field: Field },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Number(Value),
+    Op(Op),
+    Open,
+    Close,
+    Comma,
+    End,
+}
+
+impl Filter {
+    // `=`, `!=`, `<`, `<=`, `>`, `>=`, `IN (..)`, `NOT IN (..)` and `EXISTS`
+    // joined by `AND`, `OR`, `NOT` and brackets. values are "strings",
+    // numbers, true, false or null
+    pub fn parse(filter: &str) -> Result<Filter> {
+        let mut parser = Parser {
+            tokens: tokenize(filter)?,
+            next: 0,
+        };
+
+        if parser.peek() == &Token::End {
+            return Err(invalid(1, "no filter entered"));
+        }
+
+        let parsed = parser.or()?;
+
+        match parser.peek() {
+            Token::End => Ok(parsed),
+            token => Err(invalid(
+                parser.position(),
+                &format!("expected AND or OR, found {}", describe(token)),
+            )),
+        }
+    }
+
+    // fields the schema declares have to be filterable and compared with
+    // values of their type, other fields can be compared with anything
+    pub fn check(&self, schema: &Schema) -> Result<()> {
+        match self {
+            Filter::And(left, right) | Filter::Or(left, right) => {
+                left.check(schema)?;
+                right.check(schema)
+            }
+            Filter::Not(filter) => filter.check(schema),
+            Filter::Compare { field, op, value } => {
+                check_field(schema, field, Some(*op), std::slice::from_ref(value))
+            }
+            Filter::In { field, values } => check_field(schema, field, Some(Op::Eq), values),
+            Filter::Exists { field } => check_field(schema, field, None, &[]),
+        }
+    }
+
+    // the filter as a condition on the rows of `table`, with its values
+    // added to `values` as numbered params
+    pub(crate) fn to_sql(&self, table: &str, values: &mut Vec<SqlValue>) -> String {
+        match self {
+            Filter::And(left, right) => format!(
+                "({} AND {})",
+                left.to_sql(table, values),
+                right.to_sql(table, values)
+            ),
+            Filter::Or(left, right) => format!(
+                "({} OR {})",
+                left.to_sql(table, values),
+                right.to_sql(table, values)
+            ),
+            Filter::Not(filter) => format!("NOT {}", filter.to_sql(table, values)),
+            Filter::Compare {
+                field,
+                op: Op::Eq,
+                value: Value::Null,
+            } => format!("NOT {}", exists(table, field, "`type` != 'null'", values)),
+            Filter::Compare {
+                field,
+                op: Op::Ne,
+                value: Value::Null,
+            } => exists(table, field, "`type` != 'null'", values),
+            Filter::Compare {
+                field,
+                op: Op::Ne,
+                value,
+            } => {
+                let path = param(values, SqlValue::Text(json_path(&field.name)));
+                let condition = matches(Op::Eq, value, values);
+
+                format!("NOT {}", exists_at(table, &path, &condition))
+            }
+            Filter::Compare { field, op, value } => {
+                let path = param(values, SqlValue::Text(json_path(&field.name)));
+                let condition = matches(*op, value, values);
+
+                exists_at(table, &path, &condition)
+            }
+            Filter::In { field, values: any } => {
+                let path = param(values, SqlValue::Text(json_path(&field.name)));
+                let conditions: Vec<String> = any
+                    .iter()
+                    .map(|value| matches(Op::Eq, value, values))
+                    .collect();
+
+                exists_at(table, &path, &format!("({})", conditions.join(" OR ")))
+            }
+            Filter::Exists { field } => exists(table, field, "`type` != 'null'", values),
+        }
+    }
+}
+
+fn check_field(schema: &Schema, field: &Field, op: Option<Op>, values: &[Value]) -> Result<()> {
+    let declared = match schema.field(&field.name) {
+        Some(declared) => declared,
+        None => return Ok(()),
+    };
+
+    if !declared.filterable {
+        return Err(invalid(
+            field.position,
+            &format!("`{}` isn't filterable", field.name),
+        ));
+    }
+
+    for value in values.iter().filter(|value| !value.is_null()) {
+        let valid = match declared.field_type {
+            FieldType::Text | FieldType::Keyword | FieldType::Date => value.is_string(),
+            FieldType::Integer | FieldType::Float => value.is_number(),
+            FieldType::Bool => value.is_boolean() && matches!(op, Some(Op::Eq | Op::Ne)),
+            FieldType::Geo => false,
+        };
+
+        if !valid {
+            return Err(invalid(
+                field.position,
+                &format!(
+                    "`{}` is a {} field and can't be compared with {}",
+                    field.name, declared.field_type, value
+                ),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// the json type has to match too, so `"10" < 9` can't compare text with numbers
+fn matches(op: Op, value: &Value, values: &mut Vec<SqlValue>) -> String {
+    match value {
+        Value::Bool(true) => String::from("`type` = 'true'"),
+        Value::Bool(false) => String::from("`type` = 'false'"),
+        Value::Null => String::from("`type` = 'null'"),
+        Value::Number(number) => {
+            let number = match number.as_i64() {
+                Some(number) => SqlValue::Integer(number),
+                None => SqlValue::Real(number.as_f64().unwrap_or_default()),
+            };
+
+            format!(
+                "(`type` IN ('integer', 'real') AND `value` {} {})",
+                op,
+                param(values, number)
+            )
+        }
+        value => format!(
+            "(`type` = 'text' AND `value` {} {})",
+            op,
+            param(
+                values,
+                SqlValue::Text(value.as_str().unwrap_or_default().to_string())
+            )
+        ),
+    }
+}
+
+// an object is there and isn't null, even though it has no value to compare
+fn exists(table: &str, field: &Field, condition: &str, values: &mut Vec<SqlValue>) -> String {
+    let path = param(values, SqlValue::Text(json_path(&field.name)));
+
+    format!(
+        "(json_type(`{table}`.`data`, {path}) IS 'object' OR {exists})",
+        table = table,
+        path = path,
+        exists = exists_at(table, &path, condition)
+    )
+}
+
+// json_each gives one row for a plain value and one per item for an array,
+// but one per field for an object, so objects are never compared with
+// the values of their fields
+fn exists_at(table: &str, path: &str, condition: &str) -> String {
+    format!(
+        "(json_type(`{table}`.`data`, {path}) IS NOT 'object' \
+        AND EXISTS (SELECT 1 FROM json_each(`{table}`.`data`, {path}) WHERE {condition}))",
+        table = table,
+        path = path,
+        condition = condition
+    )
+}
+
+fn param(values: &mut Vec<SqlValue>, value: SqlValue) -> String {
+    values.push(value);
+    format!("?{}", values.len())
+}
+
+struct Parser {
+    // each token with the column it starts at
+    tokens: Vec<(Token, usize)>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.next].0
+    }
+
+    fn position(&self) -> usize {
+        self.tokens[self.next].1
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.next].0.clone();
+
+        if token != Token::End {
+            self.next += 1;
+        }
+
+        token
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Token::Word(word) if word.eq_ignore_ascii_case(keyword) => {
+                self.advance();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, expected: Token, description: &str) -> Result<()> {
+        match self.peek() == &expected {
+            true => {
+                self.advance();
+                Ok(())
+            }
+            false => Err(self.unexpected(description)),
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> Error {
+        invalid(
+            self.position(),
+            &format!("expected {}, found {}", expected, describe(self.peek())),
+        )
+    }
+
+    fn or(&mut self) -> Result<Filter> {
+        let mut filter = self.and()?;
+
+        while self.keyword("or") {
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Filter> {
+        let mut filter = self.unary()?;
+
+        while self.keyword("and") {
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+
+        Ok(filter)
+    }
+
+    fn unary(&mut self) -> Result<Filter> {
+        if self.keyword("not") {
+            return Ok(Filter::Not(Box::new(self.unary()?)));
+        }
+
+        if self.peek() == &Token::Open {
+            self.advance();
+            let filter = self.or()?;
+            self.expect(Token::Close, "`)`")?;
+
+            return Ok(filter);
+        }
+
+        self.predicate()
+    }
+
+    fn predicate(&mut self) -> Result<Filter> {
+        let position = self.position();
+        let field = match self.peek() {
+            Token::Word(word) if !is_keyword(word) && is_field_name(word) => Field {
+                name: word.to_string(),
+                position,
+            },
+            _ => return Err(self.unexpected("a field name")),
+        };
+        self.advance();
+
+        if self.keyword("exists") {
+            return Ok(Filter::Exists { field });
+        }
+
+        if self.keyword("not") {
+            if !self.keyword("in") {
+                return Err(self.unexpected("IN"));
+            }
+
+            return Ok(Filter::Not(Box::new(Filter::In {
+                field,
+                values: self.list()?,
+            })));
+        }
+
+        if self.keyword("in") {
+            return Ok(Filter::In {
+                field,
+                values: self.list()?,
+            });
+        }
+
+        let op = match self.peek() {
+            Token::Op(op) => *op,
+            _ => return Err(self.unexpected("an operator like = or <, IN or EXISTS")),
+        };
+        self.advance();
+
+        let position = self.position();
+        let value = self.value()?;
+
+        if (value.is_null() || value.is_boolean()) && !matches!(op, Op::Eq | Op::Ne) {
+            return Err(invalid(
+                position,
+                &format!("{} can only be compared with = or !=", value),
+            ));
+        }
+
+        Ok(Filter::Compare { field, op, value })
+    }
+
+    fn list(&mut self) -> Result<Vec<Value>> {
+        self.expect(Token::Open, "`(`")?;
+
+        let mut values = vec![self.value()?];
+
+        while self.peek() == &Token::Comma {
+            self.advance();
+            values.push(self.value()?);
+        }
+
+        self.expect(Token::Close, "`,` or `)`")?;
+
+        Ok(values)
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        let value = match self.peek() {
+            Token::Text(text) => Value::String(text.to_string()),
+            Token::Number(number) => number.clone(),
+            Token::Word(word) if word.eq_ignore_ascii_case("true") => Value::Bool(true),
+            Token::Word(word) if word.eq_ignore_ascii_case("false") => Value::Bool(false),
+            Token::Word(word) if word.eq_ignore_ascii_case("null") => Value::Null,
+            _ => return Err(self.unexpected("a value like \"text\", 5, true or null")),
+        };
+        self.advance();
+
+        Ok(value)
+    }
+}
+
+fn tokenize(filter: &str) -> Result<Vec<(Token, usize)>> {
+    let chars: Vec<char> = filter.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let position = i + 1;
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let token = match c {
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            '=' => Token::Op(Op::Eq),
+            '!' if chars.get(i + 1) == Some(&'=') => {
+                i += 1;
+                Token::Op(Op::Ne)
+            }
+            '<' | '>' => {
+                let equals = chars.get(i + 1) == Some(&'=');
+                let op = match (c, equals) {
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    (_, false) => Op::Gt,
+                    (_, true) => Op::Ge,
+                };
+
+                if equals {
+                    i += 1;
+                }
+
+                Token::Op(op)
+            }
+            '"' | '\'' => {
+                let mut text = String::new();
+
+                loop {
+                    i += 1;
+
+                    match chars.get(i) {
+                        Some('\\') if i + 1 < chars.len() => {
+                            i += 1;
+                            text.push(chars[i]);
+                        }
+                        Some(quote) if *quote == c => break,
+                        Some(next) => text.push(*next),
+                        None => return Err(invalid(position, "missing closing quote")),
+                    }
+                }
+
+                Token::Text(text)
+            }
+            c if is_word_char(c) => {
+                let start = i;
+
+                while chars.get(i + 1).is_some_and(|c| is_word_char(*c)) {
+                    i += 1;
+                }
+
+                let word: String = chars[start..=i].iter().collect();
+
+                match serde_json::from_str::<Value>(&word) {
+                    Ok(number @ Value::Number(_)) => Token::Number(number),
+                    _ => Token::Word(word),
+                }
+            }
+            c => return Err(invalid(position, &format!("unexpected `{}`", c))),
+        };
+
+        tokens.push((token, position));
+        i += 1;
+    }
+
+    tokens.push((Token::End, chars.len() + 1));
+
+    Ok(tokens)
+}
+
+// field name characters, plus `+` for numbers like `1e+5`
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '+')
+}
+
+fn is_keyword(word: &str) -> bool {
+    ["and", "or", "not", "in", "exists", "true", "false", "null"]
+        .iter()
+        .any(|keyword| word.eq_ignore_ascii_case(keyword))
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Word(word) => format!("`{}`", word),
+        Token::Text(text) => format!("\"{}\"", text),
+        Token::Number(number) => format!("`{}`", number),
+        Token::Op(op) => format!("`{}`", op),
+        Token::Open => String::from("`(`"),
+        Token::Close => String::from("`)`"),
+        Token::Comma => String::from("`,`"),
+        Token::End => String::from("the end of the filter"),
+    }
+}
+
+fn invalid(position: usize, reason: &str) -> Error {
+    Error::InvalidFilter {
+        position,
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bulk::BulkOptions;
+    use crate::engine::Engine;
+    use crate::index::SearchOptions;
+    use serde_json::json;
+
+    // the filter as nested brackets, so precedence is easy to read
+    fn shape(filter: &Filter) -> String {
+        match filter {
+            Filter::And(left, right) => format!("(and {} {})", shape(left), shape(right)),
+            Filter::Or(left, right) => format!("(or {} {})", shape(left), shape(right)),
+            Filter::Not(filter) => format!("(not {})", shape(filter)),
+            Filter::Compare { field, op, value } => format!("{}{}{}", field.name, op, value),
+            Filter::In { field, values } => format!("{} in {}", field.name, json!(values)),
+            Filter::Exists { field } => format!("{} exists", field.name),
+        }
+    }
+
+    fn parsed(filter: &str) -> String {
+        shape(&Filter::parse(filter).unwrap())
+    }
+
+    fn error(filter: &str) -> (usize, String) {
+        match Filter::parse(filter) {
+            Err(Error::InvalidFilter { position, reason }) => (position, reason),
+            result => panic!("expected an invalid filter, got {:?}", result),
+        }
+    }
+
+    // the keys of the shoes the filter keeps
+    fn keys(filter: &str) -> Vec<String> {
+        found(
+            &[
+                json!({"sku": "a", "brand": "nike", "price": 30, "tags": ["red", "sale"]}),
+                json!({"sku": "b", "brand": "adidas", "price": 80, "tags": ["blue"]}),
+                json!({"sku": "c", "brand": "puma", "price": null}),
+                json!({"sku": "d", "brand": "nike"}),
+            ],
+            filter,
+        )
+    }
+
+    fn found(shoes: &[Value], filter: &str) -> Vec<String> {
+        let engine = Engine::open_in_memory().unwrap();
+
+        engine
+            .init_index("shoes", "sku", shoes, BulkOptions::default())
+            .unwrap();
+
+        let options = SearchOptions {
+            filter: Some(Filter::parse(filter).unwrap()),
+            ..Default::default()
+        };
+        let mut keys: Vec<String> = engine
+            .index("shoes")
+            .unwrap()
+            .search("", &options)
+            .unwrap()
+            .hits
+            .into_iter()
+            .map(|hit| hit.key)
+            .collect();
+
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(parsed("a = 1 OR b = 2 AND c = 3"), "(or a=1 (and b=2 c=3))");
+        assert_eq!(parsed("a = 1 AND b = 2 OR c = 3"), "(or (and a=1 b=2) c=3)");
+        assert_eq!(
+            parsed("(a = 1 OR b = 2) AND c = 3"),
+            "(and (or a=1 b=2) c=3)"
+        );
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and() {
+        assert_eq!(parsed("NOT a = 1 AND b = 2"), "(and (not a=1) b=2)");
+        assert_eq!(parsed("not (a = 1 and b = 2)"), "(not (and a=1 b=2))");
+        assert_eq!(parsed("NOT NOT a EXISTS"), "(not (not a exists))");
+    }
+
+    #[test]
+    fn lists_and_exists() {
+        assert_eq!(
+            parsed(r#"brand IN ("nike", 'puma', 5)"#),
+            r#"brand in ["nike","puma",5]"#
+        );
+        assert_eq!(
+            parsed(r#"brand NOT IN ("nike")"#),
+            r#"(not brand in ["nike"])"#
+        );
+        assert_eq!(parsed("brand.name exists"), "brand.name exists");
+    }
+
+    #[test]
+    fn quotes_can_be_escaped() {
+        assert_eq!(parsed(r#"title = "say \"hi\"""#), r#"title="say \"hi\"""#);
+        assert_eq!(parsed(r"title = 'it\'s'"), r#"title="it's""#);
+        assert_eq!(parsed(r#"title = "it's""#), r#"title="it's""#);
+    }
+
+    #[test]
+    fn errors_point_at_the_column() {
+        assert_eq!(error(""), (1, String::from("no filter entered")));
+        assert_eq!(error("price # 5").0, 7);
+        assert_eq!(
+            error(r#"title = "red"#),
+            (9, String::from("missing closing quote"))
+        );
+        assert_eq!(
+            error("price < 5 brand"),
+            (11, String::from("expected AND or OR, found `brand`"))
+        );
+        assert_eq!(
+            error("price <"),
+            (
+                8,
+                String::from(
+                    "expected a value like \"text\", 5, true or null, found the end of the filter"
+                )
+            )
+        );
+        assert_eq!(
+            error("in_stock < true"),
+            (12, String::from("true can only be compared with = or !="))
+        );
+        assert_eq!(
+            error("brand NOT = 1"),
+            (11, String::from("expected IN, found `=`"))
+        );
+        assert_eq!(
+            error("(price < 5"),
+            (
+                11,
+                String::from("expected `)`, found the end of the filter")
+            )
+        );
+    }
+
+    #[test]
+    fn null_matches_missing_fields() {
+        assert_eq!(keys("price = null"), ["c", "d"]);
+        assert_eq!(keys("price != null"), ["a", "b"]);
+        assert_eq!(keys("price EXISTS"), ["a", "b"]);
+        assert_eq!(keys("NOT price EXISTS"), ["c", "d"]);
+    }
+
+    #[test]
+    fn lists_match_any_value() {
+        assert_eq!(keys(r#"brand IN ("nike", "puma")"#), ["a", "c", "d"]);
+        assert_eq!(keys(r#"brand NOT IN ("nike", "puma")"#), ["b"]);
+        assert_eq!(keys(r#"tags IN ("blue", "sale")"#), ["a", "b"]);
+    }
+
+    #[test]
+    fn arrays_match_when_any_item_does() {
+        assert_eq!(keys(r#"tags = "sale""#), ["a"]);
+        assert_eq!(keys(r#"tags != "sale""#), ["b", "c", "d"]);
+    }
+
+    #[test]
+    fn precedence_holds_in_the_query() {
+        assert_eq!(keys(r#"NOT brand = "nike" OR price < 50"#), ["a", "b", "c"]);
+        assert_eq!(keys(r#"NOT (brand = "nike" OR price < 50)"#), ["b", "c"]);
+        assert_eq!(
+            keys(r#"brand = "nike" AND price < 50 OR price > 50"#),
+            ["a", "b"]
+        );
+    }
+
+    #[test]
+    fn objects_arent_compared_with_their_fields() {
+        let shoes = [
+            json!({"sku": "a", "brand": "nike"}),
+            json!({"sku": "b", "brand": {"name": "nike"}}),
+            json!({"sku": "c", "brand": {}}),
+            json!({"sku": "d", "brand": [{"name": "nike"}, "nike"]}),
+        ];
+
+        assert_eq!(found(&shoes, r#"brand = "nike""#), ["a", "d"]);
+        assert_eq!(found(&shoes, r#"brand IN ("nike")"#), ["a", "d"]);
+        assert_eq!(found(&shoes, r#"brand != "nike""#), ["b", "c"]);
+        assert_eq!(found(&shoes, r#"brand.name = "nike""#), ["b"]);
+        assert_eq!(found(&shoes, "brand EXISTS"), ["a", "b", "c", "d"]);
+        assert_eq!(found(&shoes, "brand = null"), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,190 @@
+use crate::error::Result;
+use crate::search;
+use rusqlite::Connection;
+use strum_macros::{Display, EnumString};
+
+// how the distance between two terms is counted
+#[derive(Display, EnumString, Debug, Clone, Copy, PartialEq)]
+#[strum(serialize_all = "lowercase")]
+pub enum Metric {
+    // inserts, deletes and substitutions
+    #[strum(ascii_case_insensitive)]
+    Levenshtein,
+    // also swaps of two letters next to each other, so `shoe` is one from `sheo`
+    #[strum(ascii_case_insensitive)]
+    Damerau,
+}
+
+// lets a search term match terms of the index that are a few edits away
+#[derive(Debug, Clone, Copy)]
+pub struct Fuzzy {
+    // the most edits a long term can be off by, short terms get fewer
+    pub distance: usize,
+    pub metric: Metric,
+}
+
+impl Default for Fuzzy {
+    fn default() -> Self {
+        Self {
+            distance: 2,
+            metric: Metric::Damerau,
+        }
+    }
+}
+
+impl Fuzzy {
+    // no edits for terms up to 2 letters, 1 up to 5, 2 up to 9 and 3 after
+    // that, so short words don't match half the dictionary
+    pub fn edits(&self, length: usize) -> usize {
+        let edits = match length {
+            0..=2 => 0,
+            3..=5 => 1,
+            6..=9 => 2,
+            _ => 3,
+        };
+
+        edits.min(self.distance)
+    }
+
+    // the terms of the index's dictionary that are close to `word`, without
+    // `word` itself
+    pub fn similar_terms(&self, conn: &Connection, index: &str, word: &str) -> Result<Vec<String>> {
+        let word: Vec<char> = word.to_lowercase().chars().collect();
+        let edits = self.edits(word.len());
+
+        if edits == 0 {
+            return Ok(vec![]);
+        }
+
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT `term` FROM `{table}` WHERE length(`term`) BETWEEN ?1 AND ?2",
+            table = search::terms_table_name(index)
+        ))?;
+        let rows = stmt.query_map(
+            [word.len().saturating_sub(edits), word.len() + edits],
+            |row| row.get::<_, String>(0),
+        )?;
+
+        let mut terms = Vec::new();
+
+        for row in rows {
+            let term = row?;
+            let chars: Vec<char> = term.chars().collect();
+
+            if chars != word && distance(&word, &chars, self.metric) <= edits {
+                terms.push(term);
+            }
+        }
+
+        Ok(terms)
+    }
+}
+
+// the edits between two terms. a swap only counts for damerau when neither
+// letter is edited again, which is all a typo needs
+pub fn distance(a: &[char], b: &[char], metric: Metric) -> usize {
+    let width = b.len() + 1;
+    let mut rows = vec![0; (a.len() + 1) * width];
+
+    for i in 0..=a.len() {
+        rows[i * width] = i;
+    }
+    for (j, edits) in rows.iter_mut().take(width).enumerate() {
+        *edits = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut edits = (rows[(i - 1) * width + j] + 1)
+                .min(rows[i * width + j - 1] + 1)
+                .min(rows[(i - 1) * width + j - 1] + cost);
+
+            if metric == Metric::Damerau
+                && i > 1
+                && j > 1
+                && a[i - 1] == b[j - 2]
+                && a[i - 2] == b[j - 1]
+            {
+                edits = edits.min(rows[(i - 2) * width + j - 2] + 1);
+            }
+
+            rows[i * width + j] = edits;
+        }
+    }
+
+    rows[a.len() * width + b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn between(a: &str, b: &str, metric: Metric) -> usize {
+        let a: Vec<char> = a.chars().collect();
+        let b: Vec<char> = b.chars().collect();
+
+        distance(&a, &b, metric)
+    }
+
+    #[test]
+    fn damerau_counts_a_swap_as_one_edit() {
+        assert_eq!(between("shoe", "sheo", Metric::Damerau), 1);
+        assert_eq!(between("shoe", "sheo", Metric::Levenshtein), 2);
+        assert_eq!(between("ab", "ba", Metric::Damerau), 1);
+        assert_eq!(between("ab", "ba", Metric::Levenshtein), 2);
+    }
+
+    #[test]
+    fn swapped_letters_edited_again_count_separately() {
+        // optimal string alignment, `ca` to `abc` would be 2 with unrestricted damerau
+        assert_eq!(between("ca", "abc", Metric::Damerau), 3);
+        assert_eq!(between("ca", "abc", Metric::Levenshtein), 3);
+    }
+
+    #[test]
+    fn both_metrics_agree_without_swaps() {
+        for metric in [Metric::Damerau, Metric::Levenshtein] {
+            assert_eq!(between("kitten", "sitting", metric), 3);
+            assert_eq!(between("shoes", "shoes", metric), 0);
+            assert_eq!(between("shoes", "shoe", metric), 1);
+            assert_eq!(between("héllo", "hello", metric), 1);
+        }
+    }
+
+    #[test]
+    fn empty_terms_are_their_length_away() {
+        for metric in [Metric::Damerau, Metric::Levenshtein] {
+            assert_eq!(between("", "", metric), 0);
+            assert_eq!(between("", "shoe", metric), 4);
+            assert_eq!(between("shoe", "", metric), 4);
+        }
+    }
+
+    #[test]
+    fn longer_terms_allow_more_edits() {
+        let fuzzy = Fuzzy {
+            distance: 3,
+            metric: Metric::Damerau,
+        };
+        let edits: Vec<usize> = [0, 2, 3, 5, 6, 9, 10, 20]
+            .iter()
+            .map(|length| fuzzy.edits(*length))
+            .collect();
+
+        assert_eq!(edits, [0, 0, 1, 1, 2, 2, 3, 3]);
+    }
+
+    #[test]
+    fn edits_are_capped_by_the_distance() {
+        let fuzzy = Fuzzy {
+            distance: 1,
+            ..Fuzzy::default()
+        };
+
+        assert_eq!(fuzzy.edits(2), 0);
+        assert_eq!(fuzzy.edits(4), 1);
+        assert_eq!(fuzzy.edits(12), 1);
+        assert_eq!(Fuzzy::default().edits(12), 2);
+    }
+}
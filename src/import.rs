@@ -0,0 +1,161 @@
+use crate::bulk::Batch;
+use crate::error::{Error, Result};
+use crate::index::Index;
+use crate::schema;
+use serde::de::{Deserializer, SeqAccess, Visitor};
+use serde_json::{Map, Value};
+use std::fmt::Formatter;
+use std::io::{BufRead, BufReader, Read};
+use strum_macros::{Display, EnumString};
+
+#[derive(Display, EnumString, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum Format {
+    #[strum(ascii_case_insensitive)]
+    Json,
+    #[strum(ascii_case_insensitive, serialize = "ndjson", serialize = "jsonl")]
+    Ndjson,
+    #[strum(ascii_case_insensitive)]
+    Csv,
+}
+
+impl Format {
+    pub fn from_path(path: &str) -> Option<Format> {
+        let (_, extension) = path.rsplit_once('.')?;
+
+        extension.parse().ok()
+    }
+}
+
+// adds each record as it is read, records whose key already exists are
+// skipped and records that can't be read or have no key are failed
+pub(crate) fn import<R: Read>(
+    index: &Index,
+    batch: &mut Batch,
+    reader: R,
+    format: Format,
+    key_field: &str,
+) -> Result<()> {
+    let schema = index.schema()?;
+    let add = |document: Value| -> Result<bool> {
+        let key = document_key(index.name(), &document, key_field)?;
+
+        match index.insert_document(&key, &document, &schema) {
+            Ok(()) => Ok(true),
+            Err(Error::EntryExists { .. }) => Ok(false),
+            Err(err) => Err(err),
+        }
+    };
+
+    match format {
+        Format::Json => {
+            let mut deserializer = serde_json::Deserializer::from_reader(reader);
+            let mut failure = None;
+
+            let result = deserializer.deserialize_seq(Records {
+                add: &mut |document| match batch.record(|| add(document)) {
+                    Ok(()) => true,
+                    Err(err) => {
+                        failure = Some(err);
+                        false
+                    }
+                },
+            });
+
+            if let Some(err) = failure {
+                return Err(err);
+            }
+
+            // a broken array can't be read past, the records before it are kept
+            if let Err(err) = result {
+                batch.fail(Error::from(err))?;
+            }
+        }
+        Format::Ndjson => {
+            for line in BufReader::new(reader).lines() {
+                let line = line?;
+
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                batch.record(|| add(serde_json::from_str(&line)?))?;
+            }
+        }
+        Format::Csv => {
+            let mut reader = csv::Reader::from_reader(reader);
+            let headers = reader.headers()?.clone();
+
+            for row in reader.records() {
+                batch.record(|| {
+                    let row = row?;
+                    let mut fields = Map::new();
+                    let mut errors = Vec::new();
+
+                    // empty cells are left out, so exported csv files
+                    // import back without the fields documents never had
+                    for (name, text) in headers.iter().zip(row.iter()) {
+                        if text.is_empty() {
+                            continue;
+                        }
+
+                        match schema.read_cell(name, text) {
+                            Ok(value) => {
+                                fields.insert(name.to_string(), value);
+                            }
+                            Err(error) => errors.push(error),
+                        }
+                    }
+
+                    if !errors.is_empty() {
+                        return Err(Error::InvalidDocument {
+                            index: index.name().to_string(),
+                            errors,
+                        });
+                    }
+
+                    add(Value::Object(fields))
+                })?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// keys can be strings or numbers, anything else can't be stored as a key.
+// a dotted key field like `brand.id` is read from the nested object
+pub(crate) fn document_key(index: &str, document: &Value, key_field: &str) -> Result<String> {
+    match document.pointer(&schema::pointer(key_field)) {
+        Some(Value::String(key)) => Ok(key.to_string()),
+        Some(Value::Number(key)) => Ok(key.to_string()),
+        _ => Err(Error::MissingKey {
+            index: index.to_string(),
+            field: key_field.to_string(),
+        }),
+    }
+}
+
+// walks a json array one element at a time instead of building the whole vec
+struct Records<'a> {
+    // returns false to stop reading
+    add: &'a mut dyn FnMut(Value) -> bool,
+}
+
+impl<'de, 'a> Visitor<'de> for Records<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "a json array of documents")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<(), A::Error> {
+        while let Some(document) = seq.next_element()? {
+            if !(self.add)(document) {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
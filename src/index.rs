@@ -0,0 +1,838 @@
+use crate::bulk::{Batch, BulkOptions, BulkSummary};
+use crate::catalog::{self, IndexInfo, KeyCheck, NOW};
+use crate::error::{Error, Result};
+use crate::export::{self, Exporter};
+use crate::facet::{self, Facet, FacetCounts};
+use crate::filter::Filter;
+use crate::fuzzy::Fuzzy;
+use crate::import::{self, Format};
+use crate::patch::{self, PatchFormat};
+use crate::query::{is_field_name, parse_query, Term};
+use crate::rank::{self, Direction, RankSettings, Rule};
+use crate::schema::{self, FieldType, Schema};
+use crate::search;
+use rusqlite::{Connection, ErrorCode, OptionalExtension, Row};
+use serde_json::{json, Map, Value};
+use std::io::{Read, Write};
+
+pub struct Index<'a> {
+    conn: &'a Connection,
+    name: String,
+}
+
+#[derive(Debug)]
+pub struct Hit {
+    pub key: String,
+    pub document: Value,
+    // starts at 1 and goes up with every change to the entry
+    pub version: u64,
+    // entries saved before versions existed have no modified time
+    pub modified: Option<String>,
+}
+
+// how a search is narrowed down beyond its query
+#[derive(Default, Debug, Clone)]
+pub struct SearchOptions {
+    // only entries matching the filter are found, and the query can be empty
+    pub filter: Option<Filter>,
+    // sorted by these fields first and then by relevance
+    pub sort: Vec<(String, Direction)>,
+    // at most this many hits, every hit when not set
+    pub limit: Option<usize>,
+    pub offset: usize,
+    // starts after the entry with this key, the `next` of an earlier page
+    pub after: Option<String>,
+    // terms also match similar terms, entries that match exactly come first
+    pub fuzzy: Option<Fuzzy>,
+    // the last term also matches words that start with it
+    pub prefix: bool,
+}
+
+// a query with its last word completed, and the first page of what it finds
+#[derive(Debug)]
+pub struct Suggestion {
+    pub query: String,
+    pub total: usize,
+    pub hits: Vec<Hit>,
+}
+
+// one page of a search
+#[derive(Debug)]
+pub struct SearchResults {
+    pub hits: Vec<Hit>,
+    // how many entries the search finds across every page
+    pub total: usize,
+    // the key of the last hit when more come after it
+    pub next: Option<String>,
+}
+
+impl Hit {
+    // reads `key`, `data`, `version` and `modified` from the row
+    pub(crate) fn from_row(row: &Row) -> rusqlite::Result<Result<Hit>> {
+        let data: String = row.get(1)?;
+
+        Ok(match serde_json::from_str(&data) {
+            Ok(document) => Ok(Hit {
+                key: row.get(0)?,
+                document,
+                version: row.get(2)?,
+                modified: row.get(3)?,
+            }),
+            Err(err) => Err(err.into()),
+        })
+    }
+
+    // keeps only the listed fields, `brand.name` keeps `name` inside `brand`.
+    // fields the document doesn't have are left out
+    pub fn project<S: AsRef<str>>(&mut self, fields: &[S]) {
+        let mut projected = Map::new();
+
+        'fields: for field in fields.iter().map(|field| field.as_ref()) {
+            let value = match self.document.pointer(&schema::pointer(field)) {
+                Some(value) => value.clone(),
+                None => continue,
+            };
+
+            let mut segments: Vec<&str> = field.split('.').collect();
+            let last = segments.pop().unwrap_or(field);
+            let mut target = &mut projected;
+
+            for segment in segments {
+                let entry = target
+                    .entry(segment)
+                    .or_insert_with(|| Value::Object(Map::new()));
+
+                // a parent that was kept whole already has the field
+                target = match entry {
+                    Value::Object(map) => map,
+                    _ => continue 'fields,
+                };
+            }
+
+            target.insert(last.to_string(), value);
+        }
+
+        self.document = Value::Object(projected);
+    }
+
+    // the shape every command and the api show an entry in
+    pub fn to_json(&self) -> Value {
+        json!({
+            "key": self.key,
+            "version": self.version,
+            "modified": self.modified,
+            "data": self.document,
+        })
+    }
+}
+
+impl<'a> Index<'a> {
+    pub(crate) fn new(conn: &'a Connection, name: &str) -> Self {
+        Self {
+            conn,
+            name: name.to_string(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn has_document(&self, key: &str) -> Result<bool> {
+        let result: bool = self.conn.query_row(
+            &format!(
+                "SELECT count(*) FROM `{table}` WHERE `key` = ?1",
+                table = self.name
+            ),
+            [key],
+            |row| row.get(0),
+        )?;
+
+        Ok(result)
+    }
+
+    pub fn add_document(&self, key: &str, document: &Value) -> Result<()> {
+        let schema = self.schema()?;
+
+        self.savepoint("add", || {
+            self.insert_document(key, document, &schema)?;
+            catalog::touch(self.conn, &self.name)
+        })
+    }
+
+    // adds a document without marking the index as changed, so bulk loads
+    // can load the schema and touch the catalog once
+    pub(crate) fn insert_document(
+        &self,
+        key: &str,
+        document: &Value,
+        schema: &Schema,
+    ) -> Result<()> {
+        self.check(schema, document)?;
+
+        let result = self.conn.execute(
+            &format!(
+                "INSERT INTO `{table}` (`key`, `data`, `modified`) VALUES (?1, ?2, {now})",
+                table = self.name,
+                now = NOW
+            ),
+            [key, &serde_json::to_string(document)?],
+        );
+
+        match result {
+            Err(rusqlite::Error::SqliteFailure(err, _))
+                if err.code == ErrorCode::ConstraintViolation =>
+            {
+                return Err(Error::EntryExists {
+                    index: self.name.to_string(),
+                    key: key.to_string(),
+                })
+            }
+            result => result?,
+        };
+
+        search::insert_entry(
+            self.conn,
+            &self.name,
+            self.conn.last_insert_rowid(),
+            key,
+            document,
+            schema,
+        )
+    }
+
+    // returns the new version of the entry, or None when there is no entry
+    // for the key. with `if_version` the entry has to be at that version
+    pub fn update_document(
+        &self,
+        key: &str,
+        document: &Value,
+        if_version: Option<u64>,
+    ) -> Result<Option<u64>> {
+        let schema = self.schema()?;
+        self.check(&schema, document)?;
+
+        // the entry and its search rows change together or not at all
+        self.savepoint("update", || {
+            let updated: Option<(i64, u64)> = self
+                .conn
+                .query_row(
+                    &format!(
+                        "UPDATE `{table}` SET `data` = ?1, `version` = `version` + 1, `modified` = {now}
+                        WHERE `key` = ?2 AND (?3 IS NULL OR `version` = ?3)
+                        RETURNING `rowid`, `version`",
+                        table = self.name,
+                        now = NOW
+                    ),
+                    rusqlite::params![serde_json::to_string(document)?, key, if_version],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+
+            match updated {
+                Some((rowid, _)) => {
+                    search::index_entry(self.conn, &self.name, rowid, key, document, &schema)?;
+                    catalog::touch(self.conn, &self.name)?;
+                }
+                None => self.check_version(key, if_version)?,
+            }
+
+            Ok(updated.map(|(_, version)| version))
+        })
+    }
+
+    // the entry for the key as search returns it, without fields that aren't stored
+    pub fn get_document(&self, key: &str) -> Result<Option<Hit>> {
+        let schema = self.schema()?;
+
+        Ok(self.document(key)?.map(|mut hit| {
+            schema.strip(&mut hit.document);
+            hit
+        }))
+    }
+
+    // the entry for each key in the same order, None where there isn't one
+    pub fn get_documents<S: AsRef<str>>(&self, keys: &[S]) -> Result<Vec<Option<Hit>>> {
+        let schema = self.schema()?;
+        let mut hits = Vec::new();
+
+        for key in keys {
+            hits.push(self.document(key.as_ref())?.map(|mut hit| {
+                schema.strip(&mut hit.document);
+                hit
+            }));
+        }
+
+        Ok(hits)
+    }
+
+    // applies the patch to the stored entry and returns it patched with the
+    // warning of `check_key`, or None when there is no entry for the key. the
+    // read and the write happen in one savepoint so a concurrent update can't be lost
+    pub fn patch_document(
+        &self,
+        key: &str,
+        patch: &Value,
+        format: PatchFormat,
+        if_version: Option<u64>,
+    ) -> Result<Option<(Hit, Option<String>)>> {
+        self.savepoint("patch", || match self.document(key)? {
+            Some(hit) => {
+                self.check_version(key, if_version)?;
+
+                let patched = patch::apply(&hit.document, patch, format)?;
+                let warning = self.check_key(key, &patched)?;
+                self.update_document(key, &patched, Some(hit.version))?;
+
+                Ok(self.document(key)?.map(|hit| (hit, warning)))
+            }
+            None => Ok(None),
+        })
+    }
+
+    // the keys of every entry the search finds, in ranked order
+    pub fn search_keys(&self, query: &str, options: &SearchOptions) -> Result<Vec<String>> {
+        Ok(self
+            .find(query, options)?
+            .hits
+            .into_iter()
+            .map(|hit| hit.key)
+            .collect())
+    }
+
+    // removes every entry the search finds, all or none of them, and
+    // returns how many were removed
+    pub fn remove_by_query(&self, query: &str, options: &SearchOptions) -> Result<usize> {
+        self.savepoint("by_query", || {
+            let keys = self.search_keys(query, options)?;
+
+            for key in keys.iter() {
+                self.remove_document(key, None)?;
+            }
+
+            Ok(keys.len())
+        })
+    }
+
+    // merge patches every entry the search finds, all or none of them, and
+    // returns how many were patched
+    pub fn update_by_query(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+        patch: &Value,
+    ) -> Result<usize> {
+        // anything but an object would replace every entry with the same value
+        if !patch.is_object() {
+            return Err(Error::InvalidPatch(String::from(
+                "an update by query should be an object of the fields to change",
+            )));
+        }
+
+        self.savepoint("by_query", || {
+            let keys = self.search_keys(query, options)?;
+
+            for key in keys.iter() {
+                self.patch_document(key, patch, PatchFormat::Merge, None)?;
+            }
+
+            Ok(keys.len())
+        })
+    }
+
+    // returns false when there was no entry for the key, with `if_version`
+    // the entry has to be at that version
+    pub fn remove_document(&self, key: &str, if_version: Option<u64>) -> Result<bool> {
+        // the entry and its search rows go together or not at all
+        self.savepoint("remove", || {
+            let removed: Option<i64> = self
+                .conn
+                .query_row(
+                    &format!(
+                        "DELETE FROM `{table}` WHERE `key` = ?1 AND (?2 IS NULL OR `version` = ?2)
+                        RETURNING `rowid`",
+                        table = self.name
+                    ),
+                    rusqlite::params![key, if_version],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            match removed {
+                Some(rowid) => {
+                    search::remove_entry(self.conn, &self.name, rowid)?;
+                    catalog::touch(self.conn, &self.name)?;
+                }
+                None => self.check_version(key, if_version)?,
+            }
+
+            Ok(removed.is_some())
+        })
+    }
+
+    pub fn search(&self, query: &str, options: &SearchOptions) -> Result<SearchResults> {
+        let schema = self.schema()?;
+        let mut results = self.find(query, options)?;
+
+        for hit in results.hits.iter_mut() {
+            schema.strip(&mut hit.document);
+        }
+
+        Ok(results)
+    }
+
+    // completes the last word of the query from the term dictionary, the
+    // words in the most entries first. only completions that still find
+    // entries with the rest of the query and the filter are kept
+    pub fn suggest(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+        count: usize,
+    ) -> Result<Vec<Suggestion>> {
+        let mut terms = parse_query(query)?;
+
+        let last = match terms.pop() {
+            Some(term) if term.text.chars().all(char::is_alphanumeric) => term,
+            _ => {
+                return Err(Error::InvalidQuery(
+                    "the last term has to be a word to complete".to_string(),
+                ))
+            }
+        };
+
+        let options = SearchOptions {
+            after: None,
+            prefix: false,
+            ..options.clone()
+        };
+        let mut suggestions = Vec::new();
+
+        for word in search::completions(self.conn, &self.name, &last.text)? {
+            if suggestions.len() == count {
+                break;
+            }
+
+            let completed = Term {
+                field: last.field.clone(),
+                text: word,
+            };
+            let query = terms
+                .iter()
+                .chain([&completed])
+                .map(|term| term.to_string())
+                .collect::<Vec<String>>()
+                .join(" ");
+
+            let results = self.search(&query, &options)?;
+
+            if results.total > 0 {
+                suggestions.push(Suggestion {
+                    query,
+                    total: results.total,
+                    hits: results.hits,
+                });
+            }
+        }
+
+        Ok(suggestions)
+    }
+
+    // counts the values of each field over every match of the query and
+    // filter. numeric fields are counted in ranges instead
+    pub fn facets(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+        fields: &[String],
+    ) -> Result<Vec<Facet>> {
+        let schema = self.schema()?;
+
+        if let Some(filter) = &options.filter {
+            filter.check(&schema)?;
+        }
+
+        let mut facets = Vec::new();
+
+        for field in fields.iter() {
+            if !is_field_name(field) {
+                return Err(Error::InvalidField(field.to_string()));
+            }
+
+            let declared = schema.field(field);
+
+            if declared.is_some_and(|declared| !declared.filterable) {
+                return Err(Error::InvalidQuery(format!(
+                    "`{}` isn't filterable so it can't be a facet",
+                    field
+                )));
+            }
+
+            let values = search::facet_values(self.conn, &self.name, query, options, field)?;
+
+            let numeric = match declared {
+                Some(declared) => {
+                    matches!(declared.field_type, FieldType::Integer | FieldType::Float)
+                }
+                None => !values.is_empty() && values.iter().all(|(value, _)| value.is_number()),
+            };
+
+            facets.push(Facet {
+                field: field.to_string(),
+                counts: match numeric {
+                    true => FacetCounts::Ranges(facet::ranges(&values)),
+                    false => FacetCounts::Values(values),
+                },
+            });
+        }
+
+        Ok(facets)
+    }
+
+    // streams `format` records from the reader into the index, using the value
+    // of `key_field` in each record as its key
+    pub fn import<R: Read>(
+        &self,
+        reader: R,
+        format: Format,
+        key_field: &str,
+        options: BulkOptions,
+    ) -> Result<BulkSummary> {
+        let mut batch = Batch::begin(self.conn, options)?;
+
+        if self.key_field()?.is_none() {
+            catalog::set_key_field(self.conn, &self.name, key_field)?;
+        }
+
+        import::import(self, &mut batch, reader, format, key_field)?;
+        catalog::touch(self.conn, &self.name)?;
+
+        batch.commit()
+    }
+
+    // writes every document, or only those matching `query` in ranked order,
+    // returns the number of documents written
+    pub fn export<W: Write>(
+        &self,
+        writer: W,
+        format: Format,
+        query: Option<&str>,
+    ) -> Result<usize> {
+        if let Some(query) = query {
+            let hits = self.search(query, &SearchOptions::default())?.hits;
+            let columns = export::document_columns(hits.iter().map(|hit| &hit.document));
+            let mut exporter = Exporter::new(writer, format, columns)?;
+
+            for hit in hits.iter() {
+                exporter.write(&hit.document)?;
+            }
+
+            return exporter.finish();
+        }
+
+        let columns = match format {
+            Format::Csv => export::fields(self.conn, &self.name)?,
+            _ => Vec::new(),
+        };
+        let mut exporter = Exporter::new(writer, format, columns)?;
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT `data` FROM `{table}` ORDER BY `rowid`",
+            table = self.name
+        ))?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+        for row in rows {
+            exporter.write(&serde_json::from_str(&row?)?)?;
+        }
+
+        exporter.finish()
+    }
+
+    // rebuilds the search table from the stored documents
+    pub fn sync(&self) -> Result<usize> {
+        search::rebuild_table(self.conn, &self.name)
+    }
+
+    pub fn key_field(&self) -> Result<Option<String>> {
+        Ok(catalog::entry(self.conn, &self.name)?.and_then(|entry| entry.key_field))
+    }
+
+    // the document field that init and import take keys from
+    pub fn set_key_field(&self, key_field: &str) -> Result<()> {
+        if !is_field_name(key_field) {
+            return Err(Error::InvalidField(key_field.to_string()));
+        }
+
+        catalog::set_key_field(self.conn, &self.name, key_field)
+    }
+
+    pub fn key_check(&self) -> Result<KeyCheck> {
+        Ok(catalog::entry(self.conn, &self.name)?
+            .map(|entry| entry.key_check)
+            .unwrap_or_default())
+    }
+
+    pub fn set_key_check(&self, key_check: KeyCheck) -> Result<()> {
+        catalog::set_key_check(self.conn, &self.name, key_check)
+    }
+
+    // the key a document gives itself through the key field
+    pub fn derive_key(&self, document: &Value) -> Result<String> {
+        match self.key_field()? {
+            Some(key_field) => import::document_key(&self.name, document, &key_field),
+            None => Err(Error::NoKeyField(self.name.to_string())),
+        }
+    }
+
+    // compares `key` with the key field of the document, a mismatch is an
+    // error when the index rejects them and a warning when it warns.
+    // documents without the key field, or indexes without one, always pass
+    pub fn check_key(&self, key: &str, document: &Value) -> Result<Option<String>> {
+        let entry = match catalog::entry(self.conn, &self.name)? {
+            Some(entry) if entry.key_check != KeyCheck::Off => entry,
+            _ => return Ok(None),
+        };
+        let key_field = match entry.key_field {
+            Some(key_field) => key_field,
+            None => return Ok(None),
+        };
+        let value = match import::document_key(&self.name, document, &key_field) {
+            Ok(value) if value != key => value,
+            _ => return Ok(None),
+        };
+
+        match entry.key_check {
+            KeyCheck::Reject => Err(Error::KeyMismatch {
+                index: self.name.to_string(),
+                key: key.to_string(),
+                field: key_field,
+                value,
+            }),
+            _ => Ok(Some(format!("its `{}` is '{}' instead", key_field, value))),
+        }
+    }
+
+    pub fn info(&self) -> Result<IndexInfo> {
+        let entry = catalog::entry(self.conn, &self.name)?;
+        let documents: usize = self.conn.query_row(
+            &format!("SELECT count(*) FROM `{table}`", table = self.name),
+            [],
+            |row| row.get(0),
+        )?;
+
+        Ok(IndexInfo {
+            name: self.name.to_string(),
+            key_field: entry.as_ref().and_then(|entry| entry.key_field.clone()),
+            key_check: entry
+                .as_ref()
+                .map(|entry| entry.key_check)
+                .unwrap_or_default(),
+            documents,
+            size: catalog::size(self.conn, &self.name)?,
+            created: entry.as_ref().and_then(|entry| entry.created.clone()),
+            updated: entry.and_then(|entry| entry.updated),
+        })
+    }
+
+    pub fn schema(&self) -> Result<Schema> {
+        schema::load(self.conn, &self.name)
+    }
+
+    // every stored document has to match the new schema, the search table is
+    // rebuilt as the schema decides which fields are indexed
+    pub fn set_schema(&self, schema: &Schema) -> Result<()> {
+        {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT `data` FROM `{table}` ORDER BY `rowid`",
+                table = self.name
+            ))?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+
+            for row in rows {
+                self.check(schema, &serde_json::from_str(&row?)?)?;
+            }
+        }
+
+        schema::set(self.conn, &self.name, schema)?;
+        catalog::touch(self.conn, &self.name)?;
+        self.sync()?;
+
+        Ok(())
+    }
+
+    pub fn rank_settings(&self) -> Result<RankSettings> {
+        rank::settings(self.conn, &self.name)
+    }
+
+    // a weight of 1 is the default, so setting it removes the rule, weights
+    // of 0 or less would hide or invert the matches in the field
+    pub fn set_weight(&self, field: &str, weight: f64) -> Result<()> {
+        finite("weight", weight)?;
+
+        if weight <= 0.0 {
+            return Err(Error::InvalidRank(format!(
+                "expected a weight above 0, got {}",
+                weight
+            )));
+        }
+
+        let value = match weight == 1.0 {
+            true => None,
+            false => Some(weight.to_string()),
+        };
+
+        self.set_rank_rule(Rule::Weight, field, value)
+    }
+
+    // a factor of 0 is the default, so setting it removes the rule
+    pub fn set_boost(&self, field: &str, factor: f64) -> Result<()> {
+        finite("boost", factor)?;
+
+        let value = match factor == 0.0 {
+            true => None,
+            false => Some(factor.to_string()),
+        };
+
+        self.set_rank_rule(Rule::Boost, field, value)
+    }
+
+    pub fn set_sort(&self, field: &str, direction: Direction) -> Result<()> {
+        self.set_rank_rule(Rule::Sort, field, Some(direction.to_string()))
+    }
+
+    // removes every ranking rule, or only those for `field`
+    pub fn reset_rank(&self, field: Option<&str>) -> Result<usize> {
+        let removed = rank::reset(self.conn, &self.name, field)?;
+        catalog::touch(self.conn, &self.name)?;
+
+        Ok(removed)
+    }
+
+    // search results with every stored field, filters and sorts are checked
+    // against the schema first
+    fn find(&self, query: &str, options: &SearchOptions) -> Result<SearchResults> {
+        let schema = self.schema()?;
+
+        if let Some(filter) = &options.filter {
+            filter.check(&schema)?;
+        }
+
+        for (field, _) in options.sort.iter() {
+            if !is_field_name(field) {
+                return Err(Error::InvalidField(field.to_string()));
+            }
+
+            if schema
+                .field(field)
+                .is_some_and(|declared| !declared.sortable)
+            {
+                return Err(Error::InvalidQuery(format!("`{}` isn't sortable", field)));
+            }
+        }
+
+        search::find(
+            self.conn,
+            &self.name,
+            query,
+            &self.rank_settings()?,
+            options,
+        )
+    }
+
+    fn document(&self, key: &str) -> Result<Option<Hit>> {
+        let hit = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT `key`, `data`, `version`, `modified` FROM `{table}` WHERE `key` = ?1",
+                    table = self.name
+                ),
+                [key],
+                Hit::from_row,
+            )
+            .optional()?;
+
+        hit.transpose()
+    }
+
+    // runs `write` in a savepoint that is rolled back when it fails, these
+    // nest so they also work inside a bulk load
+    fn savepoint<T, F: FnOnce() -> Result<T>>(&self, name: &str, write: F) -> Result<T> {
+        self.conn
+            .execute_batch(&format!("SAVEPOINT `{name}`", name = name))?;
+
+        let result = write();
+
+        match result {
+            Ok(_) => self
+                .conn
+                .execute_batch(&format!("RELEASE `{name}`", name = name))?,
+            Err(_) => self.conn.execute_batch(&format!(
+                "ROLLBACK TO `{name}`; RELEASE `{name}`",
+                name = name
+            ))?,
+        }
+
+        result
+    }
+
+    // fails when the entry exists at a different version than `if_version`
+    fn check_version(&self, key: &str, if_version: Option<u64>) -> Result<()> {
+        let expected = match if_version {
+            Some(expected) => expected,
+            None => return Ok(()),
+        };
+
+        let version: Option<u64> = self
+            .conn
+            .query_row(
+                &format!(
+                    "SELECT `version` FROM `{table}` WHERE `key` = ?1",
+                    table = self.name
+                ),
+                [key],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match version {
+            Some(version) if version != expected => Err(Error::VersionConflict {
+                index: self.name.to_string(),
+                key: key.to_string(),
+                expected,
+                version,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn check(&self, schema: &Schema, document: &Value) -> Result<()> {
+        schema
+            .validate(document)
+            .map_err(|errors| Error::InvalidDocument {
+                index: self.name.to_string(),
+                errors,
+            })
+    }
+
+    fn set_rank_rule(&self, rule: Rule, field: &str, value: Option<String>) -> Result<()> {
+        if !is_field_name(field) {
+            return Err(Error::InvalidField(field.to_string()));
+        }
+
+        rank::set_rule(self.conn, &self.name, rule, field, value)?;
+        catalog::touch(self.conn, &self.name)
+    }
+}
+
+// NaN and infinity would be stored as text that bm25 can't rank with
+fn finite(rule: &str, value: f64) -> Result<()> {
+    match value.is_finite() {
+        true => Ok(()),
+        false => Err(Error::InvalidRank(format!(
+            "expected a finite {}, got {}",
+            rule, value
+        ))),
+    }
+}
@@ -0,0 +1,30 @@
+mod bulk;
+mod catalog;
+mod engine;
+mod error;
+mod export;
+mod facet;
+mod filter;
+mod fuzzy;
+mod import;
+mod index;
+mod patch;
+mod query;
+mod rank;
+mod schema;
+mod search;
+
+pub use bulk::{BulkMode, BulkOptions, BulkSummary};
+pub use catalog::{IndexInfo, KeyCheck};
+pub use engine::Engine;
+pub use error::{Error, Result};
+pub use facet::{Facet, FacetCounts, FacetRange};
+pub use filter::{Field, Filter, Op};
+pub use fuzzy::{Fuzzy, Metric};
+pub use import::Format;
+pub use index::{Hit, Index, SearchOptions, SearchResults, Suggestion};
+pub use patch::PatchFormat;
+pub use query::{is_field_name, parse_query, Term};
+pub use rank::{parse_sort, Direction, RankSettings, Rule};
+pub use schema::{FieldSchema, FieldType, Schema};
+pub use search::document_fields;
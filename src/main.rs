@@ -1,27 +1,41 @@
 use anyhow::{bail, Ok, Result};
+use commands::completions::Completions;
+use commands::config::Config;
 use commands::edit::Edit;
 use commands::help::Help;
 use commands::manage::Manage;
-use strum::{Display, EnumString};
+use commands::rank::Rank;
+use commands::search::Search;
+use commands::serve::Serve;
+use strum::{Display, EnumIter, EnumString};
+use tools::config::{Output, Setting, Settings};
 use tools::gui::GUI;
-use traits::command::Command;
+use tools::report::{Report, Reported};
+use traits::command::{usage, Command, ParamError};
 
-use std::{env, str::FromStr};
+use std::{collections::HashMap, env, process, str::FromStr};
 mod commands {
+    pub mod completions;
+    pub mod config;
     pub mod edit;
     pub mod help;
     pub mod manage;
+    pub mod rank;
+    pub mod search;
+    pub mod serve;
 }
 mod tools {
+    pub mod config;
     pub mod debug;
     pub mod gui;
+    pub mod report;
     pub mod validation;
 }
 pub mod traits {
     pub mod command;
 }
 
-#[derive(Display, EnumString)]
+#[derive(Display, EnumString, EnumIter)]
 enum Commands {
     #[strum(ascii_case_insensitive)]
     Manage,
@@ -34,15 +48,73 @@ enum Commands {
     #[strum(ascii_case_insensitive)]
     Config,
     #[strum(ascii_case_insensitive)]
+    Serve,
+    #[strum(ascii_case_insensitive)]
+    Completions,
+    #[strum(ascii_case_insensitive)]
     Help,
 }
 
+impl Commands {
+    fn route(&self) -> Box<dyn Command> {
+        match self {
+            Commands::Manage => Box::new(Manage::new()),
+            Commands::Edit => Box::new(Edit::new()),
+            Commands::Search => Box::new(Search::new()),
+            Commands::Rank => Box::new(Rank::new()),
+            Commands::Config => Box::new(Config::new()),
+            Commands::Serve => Box::new(Serve::new()),
+            Commands::Completions => Box::new(Completions::new()),
+            Commands::Help => Box::new(Help::new()),
+        }
+    }
+}
+
 pub const EMPTY: String = String::new();
 
+// the exit code of a command whose entry was at a different version
+const CONFLICT: i32 = 3;
+
 fn main() -> Result<()> {
-    let args: Vec<String> = env::args().collect();
+    let (args, flags) = global_flags(env::args().collect())?;
+
+    // the config command has to run with bad settings so they can be fixed
+    let strict = !matches!(
+        args.get(1).map(|command| Commands::from_str(command)),
+        Some(Result::Ok(Commands::Config))
+    );
 
-    run_command(args)?;
+    Settings::init(&flags, strict)?;
+
+    // json outputs report failures on stdout like any other result
+    if let Err(err) = run_command(args) {
+        if err.is::<Reported>() {
+            process::exit(1);
+        }
+
+        // conflicts exit with their own code, so scripts can tell them from
+        // other failures and retry
+        let conflict = matches!(
+            err.downcast_ref::<rusty_search::Error>(),
+            Some(rusty_search::Error::VersionConflict { .. })
+        );
+
+        match (Settings::get().output, conflict) {
+            (Output::Text, false) => return Err(err),
+            (Output::Text, true) => eprintln!("Error: {:?}", err),
+            (_, false) => {
+                GUI::new().report(&Report::error(&err.to_string()));
+            }
+            (_, true) => {
+                GUI::new().report(&Report::conflict(&err.to_string()));
+            }
+        }
+
+        process::exit(match conflict {
+            true => CONFLICT,
+            false => 1,
+        });
+    }
 
     Ok(())
 }
@@ -58,14 +130,11 @@ fn run_command(args: Vec<String>) -> Result<()> {
 
     let command = Commands::from_str(command_string).unwrap_or(Commands::Help);
 
-    let mut route: Box<dyn Command> = match command {
-        Commands::Manage => Box::new(Manage::new()),
-        Commands::Edit => Box::new(Edit::new()),
-        Commands::Help => Box::new(Help::new()),
-        _ => bail!(format!("Nothing implemented for {}", command)),
-    };
+    let mut route = command.route();
+
+    Report::set_context(&command.to_string(), action);
 
-    if route.print_title() {
+    if route.print_title(action, params) && !help_requested(params) {
         GUI::new().title(&format!(
             "Running '{command}' '{action}'",
             command = command_string,
@@ -73,7 +142,75 @@ fn run_command(args: Vec<String>) -> Result<()> {
         ));
     }
 
-    route.run(action, params)?;
+    if help_requested(params) {
+        return route.action_help(command_string, action);
+    }
 
-    Ok(())
+    // param errors show how the action is meant to be called
+    match route.run(action, params) {
+        Err(err) if err.is::<ParamError>() => match route.action(action) {
+            Some(spec) => bail!(
+                "{}, usage: {} {} {}",
+                err,
+                command_string,
+                spec.name,
+                usage(&spec.rules)
+            ),
+            None => Err(err),
+        },
+        result => result,
+    }
+}
+
+// `--help` or `-h` before any `--`
+fn help_requested(params: &[String]) -> bool {
+    params
+        .iter()
+        .take_while(|param| *param != "--")
+        .any(|param| param == "--help" || param == "-h")
+}
+
+// pulls flags like `--db {path}` or `--output=json` out of the args so they can
+// be given anywhere without shifting the positional params, anything after
+// `--` is left as it is
+fn global_flags(args: Vec<String>) -> Result<(Vec<String>, HashMap<Setting, String>)> {
+    let mut remaining = Vec::with_capacity(args.len());
+    let mut flags = HashMap::new();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            remaining.push(arg);
+            remaining.extend(args.by_ref());
+            break;
+        }
+
+        let flag = match arg.strip_prefix("--") {
+            Some(flag) => flag,
+            None => {
+                remaining.push(arg);
+                continue;
+            }
+        };
+
+        let (name, value) = match flag.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (flag, None),
+        };
+
+        let setting = match Setting::from_str(name) {
+            Result::Ok(setting @ (Setting::Db | Setting::Output)) => setting,
+            _ => {
+                remaining.push(arg);
+                continue;
+            }
+        };
+
+        match value.or_else(|| args.next()) {
+            Some(value) => flags.insert(setting, value),
+            None => bail!(format!("No value entered for the flag: --{}", setting)),
+        };
+    }
+
+    Ok((remaining, flags))
 }
@@ -0,0 +1,339 @@
+use crate::error::{Error, Result};
+use serde_json::{Map, Value};
+use strum_macros::{Display, EnumString};
+
+#[derive(Display, EnumString, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum PatchFormat {
+    // rfc 7386, an object of the fields to change with null removing a field
+    #[strum(ascii_case_insensitive)]
+    Merge,
+    // rfc 6902, an array of operations like `{"op": "replace", "path": "/price", "value": 5}`
+    #[strum(ascii_case_insensitive)]
+    Json,
+}
+
+impl PatchFormat {
+    // json patches are always arrays, anything else is a merge patch
+    pub fn detect(patch: &Value) -> Self {
+        match patch.is_array() {
+            true => PatchFormat::Json,
+            false => PatchFormat::Merge,
+        }
+    }
+}
+
+// applies the patch to a copy of the document, so a failed operation leaves
+// the document as it was
+pub fn apply(document: &Value, patch: &Value, format: PatchFormat) -> Result<Value> {
+    let mut patched = document.clone();
+
+    match format {
+        PatchFormat::Merge => merge(&mut patched, patch),
+        PatchFormat::Json => {
+            let operations = match patch {
+                Value::Array(operations) => operations,
+                _ => return Err(invalid("a json patch should be an array of operations")),
+            };
+
+            for (i, operation) in operations.iter().enumerate() {
+                run(&mut patched, operation).map_err(|reason| {
+                    invalid(&format!("operation {} failed, {}", i + 1, reason))
+                })?;
+            }
+        }
+    }
+
+    Ok(patched)
+}
+
+fn merge(target: &mut Value, patch: &Value) {
+    let patch = match patch {
+        Value::Object(patch) => patch,
+        patch => {
+            *target = patch.clone();
+            return;
+        }
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+
+    if let Value::Object(map) = target {
+        for (name, value) in patch {
+            match value {
+                Value::Null => {
+                    map.remove(name);
+                }
+                value => merge(map.entry(name.as_str()).or_insert(Value::Null), value),
+            }
+        }
+    }
+}
+
+fn run(document: &mut Value, operation: &Value) -> std::result::Result<(), String> {
+    let member = |name: &str| -> std::result::Result<&str, String> {
+        operation
+            .get(name)
+            .and_then(Value::as_str)
+            .ok_or_else(|| format!("`{}` should be a string", name))
+    };
+    let value = || -> std::result::Result<Value, String> {
+        operation
+            .get("value")
+            .cloned()
+            .ok_or_else(|| String::from("`value` is missing"))
+    };
+
+    let path = member("path")?;
+
+    match member("op")? {
+        "add" => add(document, path, value()?),
+        "remove" => remove(document, path).map(|_| ()),
+        "replace" if path.is_empty() => {
+            *document = value()?;
+            Ok(())
+        }
+        "replace" => {
+            remove(document, path)?;
+            add(document, path, value()?)
+        }
+        "move" => {
+            let from = member("from")?;
+
+            if path.starts_with(from) && path[from.len()..].starts_with('/') {
+                return Err(format!("can't move {} into itself", from));
+            }
+
+            let moved = remove(document, from)?;
+            add(document, path, moved)
+        }
+        "copy" => {
+            let from = member("from")?;
+            let copied = document
+                .pointer(from)
+                .cloned()
+                .ok_or_else(|| format!("nothing at {}", from))?;
+
+            add(document, path, copied)
+        }
+        "test" => match document.pointer(path) {
+            Some(current) if *current == value()? => Ok(()),
+            Some(_) => Err(format!("{} doesn't match the tested value", path)),
+            None => Err(format!("nothing at {}", path)),
+        },
+        op => Err(format!("unknown op '{}'", op)),
+    }
+}
+
+// splits `/a/b/c` into the pointer to `/a/b` and the unescaped `c`
+fn split(path: &str) -> std::result::Result<(&str, String), String> {
+    match path.rsplit_once('/') {
+        Some((parent, last)) => Ok((parent, last.replace("~1", "/").replace("~0", "~"))),
+        None => Err(format!("'{}' isn't a json pointer", path)),
+    }
+}
+
+fn add(document: &mut Value, path: &str, value: Value) -> std::result::Result<(), String> {
+    if path.is_empty() {
+        *document = value;
+        return Ok(());
+    }
+
+    let (parent, last) = split(path)?;
+
+    match document.pointer_mut(parent) {
+        Some(Value::Object(map)) => {
+            map.insert(last, value);
+            Ok(())
+        }
+        Some(Value::Array(items)) => {
+            let i = match last.as_str() {
+                "-" => items.len(),
+                last => index(last, items.len() + 1)?,
+            };
+
+            items.insert(i, value);
+            Ok(())
+        }
+        Some(_) => Err(format!("{} isn't an object or an array", parent)),
+        None => Err(format!("nothing at {}", parent)),
+    }
+}
+
+fn remove(document: &mut Value, path: &str) -> std::result::Result<Value, String> {
+    if path.is_empty() {
+        return Err(String::from("can't remove the whole document"));
+    }
+
+    let (parent, last) = split(path)?;
+    let removed = match document.pointer_mut(parent) {
+        Some(Value::Object(map)) => map.remove(&last),
+        Some(Value::Array(items)) => {
+            let i = index(&last, items.len())?;
+            Some(items.remove(i))
+        }
+        _ => None,
+    };
+
+    removed.ok_or_else(|| format!("nothing at {}", path))
+}
+
+// array indexes are digits without leading zeros, below `len`
+fn index(segment: &str, len: usize) -> std::result::Result<usize, String> {
+    let valid = !segment.is_empty()
+        && segment.bytes().all(|b| b.is_ascii_digit())
+        && (segment == "0" || !segment.starts_with('0'));
+
+    match segment.parse::<usize>() {
+        Ok(i) if valid && i < len => Ok(i),
+        _ => Err(format!("'{}' isn't an index of the array", segment)),
+    }
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidPatch(reason.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn json_patch(document: Value, patch: Value) -> Result<Value> {
+        apply(&document, &patch, PatchFormat::Json)
+    }
+
+    fn failure(result: Result<Value>) -> String {
+        match result {
+            Err(Error::InvalidPatch(reason)) => reason,
+            result => panic!("expected an invalid patch, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn dash_appends_to_an_array() {
+        let patched = json_patch(
+            json!({"tags": ["red"]}),
+            json!([
+                {"op": "add", "path": "/tags/-", "value": "sale"},
+                {"op": "add", "path": "/tags/0", "value": "new"},
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(patched, json!({"tags": ["new", "red", "sale"]}));
+    }
+
+    #[test]
+    fn array_indexes_have_to_be_in_range() {
+        let document = json!({"tags": ["red"]});
+
+        assert_eq!(
+            json_patch(
+                document.clone(),
+                json!([{"op": "add", "path": "/tags/1", "value": 1}])
+            )
+            .unwrap(),
+            json!({"tags": ["red", 1]})
+        );
+        assert_eq!(
+            failure(json_patch(
+                document.clone(),
+                json!([{"op": "add", "path": "/tags/2", "value": 1}])
+            )),
+            "operation 1 failed, '2' isn't an index of the array"
+        );
+        assert_eq!(
+            failure(json_patch(
+                document,
+                json!([{"op": "remove", "path": "/tags/01"}])
+            )),
+            "operation 1 failed, '01' isn't an index of the array"
+        );
+    }
+
+    #[test]
+    fn a_field_cant_move_into_its_own_child() {
+        let document = json!({"brand": {"name": "nike"}});
+
+        assert_eq!(
+            failure(json_patch(
+                document.clone(),
+                json!([{"op": "move", "from": "/brand", "path": "/brand/old"}])
+            )),
+            "operation 1 failed, can't move /brand into itself"
+        );
+        assert_eq!(
+            json_patch(
+                document,
+                json!([{"op": "move", "from": "/brand", "path": "/brands"}])
+            )
+            .unwrap(),
+            json!({"brands": {"name": "nike"}})
+        );
+    }
+
+    #[test]
+    fn a_failed_test_fails_the_whole_patch() {
+        let document = json!({"price": 30});
+        let patch = json!([
+            {"op": "replace", "path": "/price", "value": 25},
+            {"op": "test", "path": "/price", "value": 30},
+        ]);
+
+        assert_eq!(
+            failure(json_patch(document.clone(), patch)),
+            "operation 2 failed, /price doesn't match the tested value"
+        );
+        assert_eq!(
+            failure(json_patch(
+                document,
+                json!([{"op": "test", "path": "/stock", "value": 1}])
+            )),
+            "operation 1 failed, nothing at /stock"
+        );
+    }
+
+    #[test]
+    fn pointers_unescape_slashes_and_tildes() {
+        let patched = json_patch(
+            json!({}),
+            json!([
+                {"op": "add", "path": "/a~1b", "value": 1},
+                {"op": "add", "path": "/c~0d", "value": 2},
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(patched, json!({"a/b": 1, "c~d": 2}));
+    }
+
+    #[test]
+    fn merge_nulls_remove_fields() {
+        let patched = apply(
+            &json!({"price": 30, "brand": {"name": "nike", "id": 1}, "tags": ["red"]}),
+            &json!({"price": null, "brand": {"id": null}, "stock": null, "tags": ["blue"]}),
+            PatchFormat::Merge,
+        )
+        .unwrap();
+
+        assert_eq!(
+            patched,
+            json!({"brand": {"name": "nike"}, "tags": ["blue"]})
+        );
+    }
+
+    #[test]
+    fn merge_objects_replace_values_that_arent_objects() {
+        let patched = apply(
+            &json!({"brand": "nike"}),
+            &json!({"brand": {"name": "nike", "id": null}}),
+            PatchFormat::Merge,
+        )
+        .unwrap();
+
+        assert_eq!(patched, json!({"brand": {"name": "nike"}}));
+    }
+}
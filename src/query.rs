@@ -0,0 +1,93 @@
+use crate::error::{Error, Result};
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub struct Term {
+    pub field: Option<String>,
+    pub text: String,
+}
+
+// written the way it's parsed, with quotes when the text has spaces or a `:`
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(field) = &self.field {
+            write!(f, "{}:", field)?;
+        }
+
+        match self.text.contains(|c: char| c.is_whitespace() || c == ':') {
+            true => write!(f, "\"{}\"", self.text),
+            false => write!(f, "{}", self.text),
+        }
+    }
+}
+
+// splits a query like `title:"red shoes" brand:nike cheap` into terms,
+// a term without a `field:` prefix is searched across every field
+pub fn parse_query(query: &str) -> Result<Vec<Term>> {
+    let mut terms = Vec::new();
+    let mut chars = query.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut field = None;
+        let mut text = String::new();
+
+        loop {
+            match chars.peek() {
+                Some(c) if c.is_whitespace() => break,
+                Some('"') => {
+                    chars.next();
+                    text.push_str(&read_phrase(&mut chars)?);
+                }
+                Some(':') if field.is_none() && is_field_name(&text) => {
+                    chars.next();
+                    field = Some(std::mem::take(&mut text));
+                }
+                Some(_) => text.push(chars.next().unwrap()),
+                None => break,
+            }
+        }
+
+        if text.trim().is_empty() {
+            match field {
+                Some(field) => {
+                    return Err(Error::InvalidQuery(format!(
+                        "no search term entered for the field: {}",
+                        field
+                    )))
+                }
+                None => continue,
+            }
+        }
+
+        terms.push(Term { field, text });
+    }
+
+    Ok(terms)
+}
+
+fn read_phrase(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String> {
+    let mut phrase = String::new();
+
+    for c in chars.by_ref() {
+        if c == '"' {
+            return Ok(phrase);
+        }
+        phrase.push(c);
+    }
+
+    Err(Error::InvalidQuery("missing closing quote".to_string()))
+}
+
+// field names are json paths like `brand` or `brand.name`
+pub fn is_field_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '-')
+}
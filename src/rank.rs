@@ -0,0 +1,185 @@
+use crate::engine::does_table_exist;
+use crate::error::{Error, Result};
+use crate::query::is_field_name;
+use rusqlite::Connection;
+use std::str::FromStr;
+use strum_macros::{Display, EnumString};
+
+#[derive(Display, EnumString, Debug, Clone, Copy)]
+#[strum(serialize_all = "lowercase")]
+pub enum Rule {
+    Weight,
+    Boost,
+    Sort,
+}
+
+#[derive(Display, EnumString, Debug, Clone, Copy)]
+#[strum(serialize_all = "lowercase")]
+pub enum Direction {
+    #[strum(ascii_case_insensitive)]
+    Asc,
+    #[strum(ascii_case_insensitive)]
+    Desc,
+}
+
+#[derive(Default, Debug)]
+pub struct RankSettings {
+    pub weights: Vec<(String, f64)>,
+    pub boosts: Vec<(String, f64)>,
+    pub sorts: Vec<(String, Direction)>,
+}
+
+// reads sorts like `price:asc,rating:desc`, a field without a direction
+// sorts ascending
+pub fn parse_sort(text: &str) -> Result<Vec<(String, Direction)>> {
+    let mut sorts = Vec::new();
+
+    for sort in text.split(',') {
+        let (field, direction) = match sort.rsplit_once(':') {
+            Some((field, direction)) => match Direction::from_str(direction) {
+                Ok(direction) => (field, direction),
+                Err(_) => {
+                    return Err(Error::InvalidQuery(format!(
+                        "`{}` isn't a sort direction, expected asc|desc",
+                        direction
+                    )))
+                }
+            },
+            None => (sort, Direction::Asc),
+        };
+
+        if !is_field_name(field) {
+            return Err(Error::InvalidField(field.to_string()));
+        }
+
+        sorts.push((field.to_string(), direction));
+    }
+
+    Ok(sorts)
+}
+
+pub fn table_name(index: &str) -> String {
+    format!("{}_rank", index)
+}
+
+pub fn create_table(conn: &Connection, index: &str) -> Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE if not exists `{table}` (
+            `rule` TEXT,
+            `field` TEXT,
+            `value` TEXT,
+            PRIMARY KEY (`rule`, `field`));
+        ",
+        table = table_name(index)
+    ))?;
+
+    Ok(())
+}
+
+pub fn drop_table(conn: &Connection, index: &str) -> Result<()> {
+    conn.execute_batch(&format!(
+        "DROP TABLE if exists `{table}`;",
+        table = table_name(index)
+    ))?;
+
+    Ok(())
+}
+
+// a value of None removes the rule, leaving the field at its default
+pub fn set_rule(
+    conn: &Connection,
+    index: &str,
+    rule: Rule,
+    field: &str,
+    value: Option<String>,
+) -> Result<()> {
+    create_table(conn, index)?;
+
+    conn.execute(
+        &format!(
+            "DELETE FROM `{table}` WHERE `rule` = ?1 AND `field` = ?2",
+            table = table_name(index)
+        ),
+        [&rule.to_string(), field],
+    )?;
+
+    if let Some(value) = value {
+        conn.execute(
+            &format!(
+                "INSERT INTO `{table}` (`rule`, `field`, `value`) VALUES (?1, ?2, ?3)",
+                table = table_name(index)
+            ),
+            [&rule.to_string(), field, &value],
+        )?;
+    }
+
+    Ok(())
+}
+
+pub fn reset(conn: &Connection, index: &str, field: Option<&str>) -> Result<usize> {
+    create_table(conn, index)?;
+
+    let removed = match field {
+        Some(field) => conn.execute(
+            &format!(
+                "DELETE FROM `{table}` WHERE `field` = ?1",
+                table = table_name(index)
+            ),
+            [field],
+        )?,
+        None => conn.execute(
+            &format!("DELETE FROM `{table}`", table = table_name(index)),
+            [],
+        )?,
+    };
+
+    Ok(removed)
+}
+
+// indexes without a rank table simply have the default settings
+pub fn settings(conn: &Connection, index: &str) -> Result<RankSettings> {
+    let mut settings = RankSettings::default();
+
+    if !does_table_exist(conn, &table_name(index))? {
+        return Ok(settings);
+    }
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT `rule`, `field`, `value` FROM `{table}` ORDER BY `rowid`",
+        table = table_name(index)
+    ))?;
+
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+        ))
+    })?;
+
+    // rows that no longer parse are skipped rather than breaking every search
+    for row in rows {
+        let (rule, field, value) = row?;
+
+        match Rule::from_str(&rule) {
+            Ok(Rule::Weight) => {
+                if let Ok(weight) = value.parse() {
+                    settings.weights.push((field, weight))
+                }
+            }
+            Ok(Rule::Boost) => {
+                if let Ok(factor) = value.parse() {
+                    settings.boosts.push((field, factor))
+                }
+            }
+            Ok(Rule::Sort) => {
+                if let Ok(direction) = Direction::from_str(&value) {
+                    settings.sorts.push((field, direction))
+                }
+            }
+            Err(_) => (),
+        }
+    }
+
+    Ok(settings)
+}
@@ -0,0 +1,458 @@
+use crate::engine::does_table_exist;
+use crate::error::{Error, Result};
+use crate::query::is_field_name;
+use rusqlite::Connection;
+use serde_json::{json, Map, Value};
+use std::str::FromStr;
+use strum_macros::{Display, EnumString};
+
+#[derive(Display, EnumString, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum FieldType {
+    // searched word by word
+    #[strum(ascii_case_insensitive)]
+    Text,
+    // a single value like a tag or an id
+    #[strum(ascii_case_insensitive)]
+    Keyword,
+    #[strum(ascii_case_insensitive)]
+    Integer,
+    #[strum(ascii_case_insensitive)]
+    Float,
+    #[strum(ascii_case_insensitive)]
+    Bool,
+    // a string like `2024-01-31` or `2024-01-31T09:30:00Z`
+    #[strum(ascii_case_insensitive)]
+    Date,
+    // an object like `{"lat": 51.5, "lon": -0.12}`
+    #[strum(ascii_case_insensitive)]
+    Geo,
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldSchema {
+    pub name: String,
+    pub field_type: FieldType,
+    // added to the search table
+    pub indexed: bool,
+    // included in search results
+    pub stored: bool,
+    pub filterable: bool,
+    pub sortable: bool,
+}
+
+impl FieldSchema {
+    // text is only searched, other types can be filtered and all but geo sorted
+    pub fn new(name: &str, field_type: FieldType) -> Self {
+        Self {
+            name: name.to_string(),
+            field_type,
+            indexed: matches!(field_type, FieldType::Text | FieldType::Keyword),
+            stored: true,
+            filterable: field_type != FieldType::Text,
+            sortable: !matches!(field_type, FieldType::Text | FieldType::Geo),
+        }
+    }
+
+    fn check(&self, value: &Value) -> Option<String> {
+        let valid = match self.field_type {
+            FieldType::Text | FieldType::Keyword => value.is_string(),
+            FieldType::Integer => value.is_i64() || value.is_u64(),
+            FieldType::Float => value.is_number(),
+            FieldType::Bool => value.is_boolean(),
+            FieldType::Date => value.as_str().is_some_and(is_date),
+            FieldType::Geo => is_geo(value),
+        };
+
+        match valid {
+            true => None,
+            false => Some(self.error(value)),
+        }
+    }
+
+    // text from a csv cell as a value of the field's type
+    fn read(&self, text: &str) -> std::result::Result<Value, String> {
+        let value = match self.field_type {
+            FieldType::Integer => match text.trim().parse::<i64>() {
+                Ok(number) => Some(Value::from(number)),
+                Err(_) => text.trim().parse::<u64>().ok().map(Value::from),
+            },
+            FieldType::Float => text
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|number| number.is_finite())
+                .map(Value::from),
+            FieldType::Bool => match text.trim() {
+                value if value.eq_ignore_ascii_case("true") => Some(Value::Bool(true)),
+                value if value.eq_ignore_ascii_case("false") => Some(Value::Bool(false)),
+                _ => None,
+            },
+            FieldType::Geo => serde_json::from_str(text).ok(),
+            _ => Some(Value::from(text)),
+        };
+
+        value.ok_or_else(|| self.error(&Value::from(text)))
+    }
+
+    fn error(&self, value: &Value) -> String {
+        format!(
+            "{}: expected {}, got {}",
+            self.name,
+            match self.field_type {
+                FieldType::Date => String::from("a date like 2024-01-31"),
+                FieldType::Geo => String::from("a point like {\"lat\": 51.5, \"lon\": -0.12}"),
+                field_type => field_type.to_string(),
+            },
+            value
+        )
+    }
+}
+
+// the declared fields of an index, fields that aren't declared are stored
+// and searched as they always were
+#[derive(Default, Debug, Clone)]
+pub struct Schema {
+    pub fields: Vec<FieldSchema>,
+}
+
+impl Schema {
+    // takes `{"title": "text", "price": {"type": "float", "sortable": false}}`
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let map = match value {
+            Value::Object(map) => map,
+            _ => return Err(invalid("expected an object of field names and types")),
+        };
+
+        let mut fields = Vec::new();
+
+        for (name, options) in map {
+            if !is_field_name(name) {
+                return Err(Error::InvalidField(name.to_string()));
+            }
+
+            let (field_type, flags) = match options {
+                Value::String(field_type) => (field_type.as_str(), &Map::new()),
+                Value::Object(options) => match options.get("type").and_then(Value::as_str) {
+                    Some(field_type) => (field_type, options),
+                    None => return Err(invalid(&format!("{} has no type", name))),
+                },
+                _ => return Err(invalid(&format!("{} should be a type or an object", name))),
+            };
+
+            let field_type = FieldType::from_str(field_type).map_err(|_| {
+                invalid(&format!(
+                    "{} has an unknown type '{}', expected text|keyword|integer|float|bool|date|geo",
+                    name, field_type
+                ))
+            })?;
+
+            let mut field = FieldSchema::new(name, field_type);
+
+            for (flag, value) in flags {
+                let value = match (flag.as_str(), value.as_bool()) {
+                    ("type", _) => continue,
+                    (_, Some(value)) => value,
+                    (_, None) => {
+                        return Err(invalid(&format!("{}.{} should be a bool", name, flag)))
+                    }
+                };
+
+                match flag.as_str() {
+                    "indexed" => field.indexed = value,
+                    "stored" => field.stored = value,
+                    "filterable" => field.filterable = value,
+                    "sortable" => field.sortable = value,
+                    _ => {
+                        return Err(invalid(&format!(
+                            "{} has an unknown option '{}'",
+                            name, flag
+                        )))
+                    }
+                }
+            }
+
+            fields.push(field);
+        }
+
+        Ok(Self { fields })
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut map = Map::new();
+
+        for field in self.fields.iter() {
+            map.insert(
+                field.name.to_string(),
+                json!({
+                    "type": field.field_type.to_string(),
+                    "indexed": field.indexed,
+                    "stored": field.stored,
+                    "filterable": field.filterable,
+                    "sortable": field.sortable,
+                }),
+            );
+        }
+
+        Value::Object(map)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    // the declared field a path is in, `brand.name` is in `brand`
+    pub fn field(&self, path: &str) -> Option<&FieldSchema> {
+        self.fields.iter().find(|field| {
+            path == field.name
+                || path
+                    .strip_prefix(field.name.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+    }
+
+    // every value of a declared field has to match its type, arrays are
+    // checked item by item and null counts as missing
+    pub fn validate(&self, document: &Value) -> std::result::Result<(), Vec<String>> {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        if !document.is_object() {
+            return Err(vec![String::from("expected a json object")]);
+        }
+
+        let mut errors = Vec::new();
+
+        for field in self.fields.iter() {
+            let value = match document.pointer(&pointer(&field.name)) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            let values = match value {
+                Value::Array(items) => items.iter().collect(),
+                value => vec![value],
+            };
+
+            for value in values {
+                if value.is_null() {
+                    continue;
+                }
+
+                if let Some(error) = field.check(value) {
+                    errors.push(error);
+                    break;
+                }
+            }
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    // csv cells are all text, so a column the schema declares is read as
+    // its type before the document is validated
+    pub(crate) fn read_cell(&self, column: &str, text: &str) -> std::result::Result<Value, String> {
+        match self.fields.iter().find(|field| field.name == column) {
+            Some(field) => field.read(text),
+            None => Ok(Value::from(text)),
+        }
+    }
+
+    pub(crate) fn is_indexed(&self, path: &str) -> bool {
+        self.field(path).is_none_or(|field| field.indexed)
+    }
+
+    // removes the fields that aren't stored from a search result
+    pub(crate) fn strip(&self, document: &mut Value) {
+        for field in self.fields.iter().filter(|field| !field.stored) {
+            let (parent, name) = match field.name.rsplit_once('.') {
+                Some((parent, name)) => (document.pointer_mut(&pointer(parent)), name),
+                None => (Some(&mut *document), field.name.as_str()),
+            };
+
+            if let Some(Value::Object(map)) = parent {
+                map.remove(name);
+            }
+        }
+    }
+}
+
+pub fn table_name(index: &str) -> String {
+    format!("{}_schema", index)
+}
+
+pub fn create_table(conn: &Connection, index: &str) -> Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE TABLE if not exists `{table}` (
+            `field` TEXT PRIMARY KEY,
+            `type` TEXT,
+            `indexed` INTEGER,
+            `stored` INTEGER,
+            `filterable` INTEGER,
+            `sortable` INTEGER);
+        ",
+        table = table_name(index)
+    ))?;
+
+    Ok(())
+}
+
+pub fn drop_table(conn: &Connection, index: &str) -> Result<()> {
+    conn.execute_batch(&format!(
+        "DROP TABLE if exists `{table}`;",
+        table = table_name(index)
+    ))?;
+
+    Ok(())
+}
+
+// replaces every field of the schema
+pub fn set(conn: &Connection, index: &str, schema: &Schema) -> Result<()> {
+    create_table(conn, index)?;
+
+    conn.execute(
+        &format!("DELETE FROM `{table}`", table = table_name(index)),
+        [],
+    )?;
+
+    let mut stmt = conn.prepare(&format!(
+        "INSERT INTO `{table}` (`field`, `type`, `indexed`, `stored`, `filterable`, `sortable`)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        table = table_name(index)
+    ))?;
+
+    for field in schema.fields.iter() {
+        stmt.execute(rusqlite::params![
+            field.name,
+            field.field_type.to_string(),
+            field.indexed,
+            field.stored,
+            field.filterable,
+            field.sortable
+        ])?;
+    }
+
+    Ok(())
+}
+
+// indexes without a schema table have no declared fields
+pub fn load(conn: &Connection, index: &str) -> Result<Schema> {
+    let mut schema = Schema::default();
+
+    if !does_table_exist(conn, &table_name(index))? {
+        return Ok(schema);
+    }
+
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT `field`, `type`, `indexed`, `stored`, `filterable`, `sortable`
+        FROM `{table}` ORDER BY `rowid`",
+        table = table_name(index)
+    ))?;
+
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, bool>(2)?,
+            row.get::<_, bool>(3)?,
+            row.get::<_, bool>(4)?,
+            row.get::<_, bool>(5)?,
+        ))
+    })?;
+
+    // like rank rules, rows that no longer parse are skipped
+    for row in rows {
+        let (name, field_type, indexed, stored, filterable, sortable) = row?;
+
+        if let Ok(field_type) = FieldType::from_str(&field_type) {
+            schema.fields.push(FieldSchema {
+                name,
+                field_type,
+                indexed,
+                stored,
+                filterable,
+                sortable,
+            });
+        }
+    }
+
+    Ok(schema)
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidSchema(reason.to_string())
+}
+
+pub(crate) fn pointer(field: &str) -> String {
+    field.split('.').fold(String::new(), |pointer, segment| {
+        format!(
+            "{}/{}",
+            pointer,
+            segment.replace('~', "~0").replace('/', "~1")
+        )
+    })
+}
+
+// `YYYY-MM-DD`, optionally followed by a time like `T09:30`, `T09:30:00.5Z`
+// or ` 09:30:00+01:00`
+fn is_date(text: &str) -> bool {
+    let digits = |text: &str| !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit());
+    let in_range = |text: &str, min: u32, max: u32| {
+        digits(text) && text.parse::<u32>().is_ok_and(|n| n >= min && n <= max)
+    };
+
+    let (date, time) = match text.find(['T', ' ']) {
+        Some(i) => (&text[..i], Some(&text[i + 1..])),
+        None => (text, None),
+    };
+
+    let valid_date = match date.split('-').collect::<Vec<&str>>().as_slice() {
+        [year, month, day] => {
+            year.len() == 4
+                && digits(year)
+                && month.len() == 2
+                && in_range(month, 1, 12)
+                && day.len() == 2
+                && in_range(day, 1, 31)
+        }
+        _ => false,
+    };
+
+    let time = match time {
+        Some(time) => time,
+        None => return valid_date,
+    };
+
+    let time = time.strip_suffix('Z').unwrap_or(time);
+    let time = match time.rfind(['+', '-']) {
+        Some(i) if in_range(&time[i + 1..].replace(':', ""), 0, 2359) => &time[..i],
+        _ => time,
+    };
+    let time = match time.split_once('.') {
+        Some((time, fraction)) if digits(fraction) => time,
+        _ => time,
+    };
+
+    valid_date
+        && match time.split(':').collect::<Vec<&str>>().as_slice() {
+            [hours, minutes] => in_range(hours, 0, 23) && in_range(minutes, 0, 59),
+            [hours, minutes, seconds] => {
+                in_range(hours, 0, 23) && in_range(minutes, 0, 59) && in_range(seconds, 0, 60)
+            }
+            _ => false,
+        }
+}
+
+fn is_geo(value: &Value) -> bool {
+    let lat = value.get("lat").and_then(Value::as_f64);
+    let lon = value.get("lon").and_then(Value::as_f64);
+
+    match (lat, lon) {
+        (Some(lat), Some(lon)) => (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon),
+        _ => false,
+    }
+}
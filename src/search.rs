@@ -0,0 +1,625 @@
+use crate::error::{Error, Result};
+use crate::index::{Hit, SearchOptions, SearchResults};
+use crate::query::parse_query;
+use crate::rank::RankSettings;
+use crate::schema::{self, Schema};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params_from_iter, Connection};
+use serde_json::Value;
+
+pub fn table_name(index: &str) -> String {
+    format!("{}_search", index)
+}
+
+// the term dictionary of the search table, with a row for every term it has
+pub fn terms_table_name(index: &str) -> String {
+    format!("{}_terms", index)
+}
+
+pub fn create_table(conn: &Connection, index: &str) -> Result<()> {
+    conn.execute_batch(&format!(
+        "DROP TABLE if exists `{table}`;
+        CREATE VIRTUAL TABLE `{table}` USING fts5(
+            `key` UNINDEXED, `field` UNINDEXED, `value`, prefix = '1 2 3'
+        );
+        ",
+        table = table_name(index)
+    ))?;
+
+    create_terms_table(conn, index)
+}
+
+// the dictionary reads the search table as it is, so it never needs a rebuild
+pub fn create_terms_table(conn: &Connection, index: &str) -> Result<()> {
+    conn.execute_batch(&format!(
+        "CREATE VIRTUAL TABLE if not exists `{terms}` USING fts5vocab(`{table}`, 'row');",
+        terms = terms_table_name(index),
+        table = table_name(index)
+    ))?;
+
+    Ok(())
+}
+
+pub fn drop_table(conn: &Connection, index: &str) -> Result<()> {
+    conn.execute_batch(&format!(
+        "DROP TABLE if exists `{terms}`;
+        DROP TABLE if exists `{table}`;
+        ",
+        terms = terms_table_name(index),
+        table = table_name(index)
+    ))?;
+
+    Ok(())
+}
+
+// the search rows of an entry are numbered from its rowid in the index table
+// shifted by this many bits, so they can be found by rowid instead of
+// scanning the unindexed key column. a VACUUM of the database can renumber
+// the index table, `search sync` numbers the rows again
+const FIELD_BITS: u32 = 20;
+
+// the rowids an entry's search rows can have
+fn rows(rowid: i64) -> (i64, i64) {
+    (rowid << FIELD_BITS, ((rowid + 1) << FIELD_BITS) - 1)
+}
+
+pub fn index_entry(
+    conn: &Connection,
+    index: &str,
+    rowid: i64,
+    key: &str,
+    document: &Value,
+    schema: &Schema,
+) -> Result<()> {
+    remove_entry(conn, index, rowid)?;
+    insert_entry(conn, index, rowid, key, document, schema)
+}
+
+// only for entries with no search rows yet. fields the schema doesn't index
+// are left out
+pub fn insert_entry(
+    conn: &Connection,
+    index: &str,
+    rowid: i64,
+    key: &str,
+    document: &Value,
+    schema: &Schema,
+) -> Result<()> {
+    let mut stmt = conn.prepare_cached(&format!(
+        "INSERT INTO `{table}` (`rowid`, `key`, `field`, `value`) VALUES (?1, ?2, ?3, ?4)",
+        table = table_name(index)
+    ))?;
+    let (first, last) = rows(rowid);
+    let mut next = first;
+
+    for (field, value) in document_fields(document) {
+        if !schema.is_indexed(&field) {
+            continue;
+        }
+
+        if next > last {
+            return Err(Error::InvalidDocument {
+                index: index.to_string(),
+                errors: vec![format!("more than {} fields to index", 1 << FIELD_BITS)],
+            });
+        }
+
+        stmt.execute(rusqlite::params![next, key, field, value])?;
+        next += 1;
+    }
+
+    Ok(())
+}
+
+pub fn remove_entry(conn: &Connection, index: &str, rowid: i64) -> Result<()> {
+    let (first, last) = rows(rowid);
+
+    conn.prepare_cached(&format!(
+        "DELETE FROM `{table}` WHERE `rowid` BETWEEN ?1 AND ?2",
+        table = table_name(index)
+    ))?
+    .execute([first, last])?;
+
+    Ok(())
+}
+
+// search tables made before rows were numbered from the entry's rowid have
+// rows below the first one an entry can have
+pub fn is_outdated(conn: &Connection, index: &str) -> Result<bool> {
+    Ok(conn.query_row(
+        &format!(
+            "SELECT EXISTS (SELECT 1 FROM `{table}` WHERE `rowid` < ?1)",
+            table = table_name(index)
+        ),
+        [rows(1).0],
+        |row| row.get(0),
+    )?)
+}
+
+// runs in one transaction so a failed rebuild keeps the old search table
+pub fn rebuild_table(conn: &Connection, index: &str) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let schema = schema::load(&tx, index)?;
+    let mut count = 0;
+
+    create_table(&tx, index)?;
+
+    {
+        let mut stmt = tx.prepare(&format!(
+            "SELECT `rowid`, `key`, `data` FROM `{table}`",
+            table = index
+        ))?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?;
+
+        for row in rows {
+            let (rowid, key, data) = row?;
+
+            insert_entry(
+                &tx,
+                index,
+                rowid,
+                &key,
+                &serde_json::from_str(&data)?,
+                &schema,
+            )?;
+            count += 1;
+        }
+    }
+
+    tx.commit()?;
+
+    Ok(count)
+}
+
+// the entries a search finds as the from and where clauses of a select, with
+// how they rank and the params they take
+struct Matches {
+    source: String,
+    conditions: Vec<String>,
+    order: Vec<String>,
+    values: Vec<SqlValue>,
+}
+
+impl Matches {
+    // a query with no terms finds every entry matching the filter
+    fn new(
+        conn: &Connection,
+        index: &str,
+        query: &str,
+        settings: &RankSettings,
+        options: &SearchOptions,
+    ) -> Result<Self> {
+        let terms = parse_query(query)?;
+
+        if terms.is_empty() && options.filter.is_none() {
+            return Err(Error::InvalidQuery(
+                "no terms entered for the search query".to_string(),
+            ));
+        }
+
+        let search = table_name(index);
+        let mut term_queries = Vec::new();
+        let mut values = Vec::new();
+
+        for (i, term) in terms.iter().enumerate() {
+            // the term as typed, then any similar words from the dictionary.
+            // phrases are only matched as typed
+            let mut texts = vec![(quote(&term.text), true)];
+
+            // the last term also matches the words it starts, as it's still
+            // being typed
+            if options.prefix && i + 1 == terms.len() {
+                texts[0].0.push('*');
+            }
+
+            if let Some(fuzzy) = &options.fuzzy {
+                if term.text.chars().all(char::is_alphanumeric) {
+                    let similar = fuzzy.similar_terms(conn, index, &term.text)?;
+
+                    if !similar.is_empty() {
+                        let similar: Vec<String> = similar.iter().map(|text| quote(text)).collect();
+                        texts.push((similar.join(" OR "), false));
+                    }
+                }
+            }
+
+            let field = match &term.field {
+                Some(field) => {
+                    values.push(SqlValue::Text(field.to_string()));
+                    format!(
+                        " AND (`field` = ?{param} OR `field` GLOB ?{param} || '.*')",
+                        param = values.len()
+                    )
+                }
+                None => String::new(),
+            };
+
+            for (text, exact) in texts {
+                values.push(SqlValue::Text(text));
+
+                // the limit stops sqlite flattening the subquery, which bm25 can't run in
+                term_queries.push(format!(
+                    "SELECT * FROM (
+                        SELECT `key`, `field`, {term} AS `term`, {exact} AS `exact`, bm25(`{search}`) AS `score`
+                        FROM `{search}` WHERE `{search}` MATCH ?{match_param}{field} LIMIT -1
+                    )",
+                    term = i,
+                    exact = exact,
+                    search = search,
+                    match_param = values.len(),
+                    field = field
+                ));
+            }
+        }
+
+        // bm25 scores are negative, so flip them to make higher more relevant
+        let mut weight = String::from("1");
+        for (field, field_weight) in settings.weights.iter() {
+            values.push(SqlValue::Text(field.to_string()));
+            weight = format!(
+                "CASE WHEN `field` = ?{param} OR `field` GLOB ?{param} || '.*' THEN {weight:?} ELSE {rest} END",
+                param = values.len(),
+                weight = field_weight,
+                rest = weight
+            );
+        }
+
+        // without terms only boosts add to the relevance
+        let mut relevance = Vec::new();
+        if !terms.is_empty() {
+            relevance.push(String::from("`matches`.`relevance`"));
+        }
+        for (field, factor) in settings.boosts.iter() {
+            values.push(SqlValue::Text(json_path(field)));
+            relevance.push(format!(
+                "{factor:?} * COALESCE(json_extract(`{table}`.`data`, ?{param}), 0)",
+                factor = factor,
+                table = index,
+                param = values.len()
+            ));
+        }
+
+        // sorts that were asked for come before relevance, with missing
+        // values last either way
+        let mut order = Vec::new();
+        for (field, direction) in options.sort.iter() {
+            values.push(SqlValue::Text(json_path(field)));
+            order.push(format!(
+                "json_extract(`{table}`.`data`, ?{param}) {direction} NULLS LAST",
+                table = index,
+                param = values.len(),
+                direction = direction
+            ));
+        }
+        // entries with more terms matched as typed come before those that
+        // needed similar words
+        if options.fuzzy.is_some() && !terms.is_empty() {
+            order.push(String::from("`matches`.`exact` DESC"));
+        }
+        if !relevance.is_empty() {
+            order.push(format!("{} DESC", relevance.join(" + ")));
+        }
+        for (field, direction) in settings.sorts.iter() {
+            values.push(SqlValue::Text(json_path(field)));
+            order.push(format!(
+                "json_extract(`{table}`.`data`, ?{param}) {direction}",
+                table = index,
+                param = values.len(),
+                direction = direction
+            ));
+        }
+        order.push(format!("`{table}`.`rowid`", table = index));
+
+        let mut conditions = Vec::new();
+
+        let source = match terms.is_empty() {
+            true => format!("`{table}`", table = index),
+            false => {
+                conditions.push(format!("`matches`.`terms` = {}", terms.len()));
+
+                format!(
+                    "(
+                        SELECT `key`, COUNT(DISTINCT `term`) AS `terms`,
+                            COUNT(DISTINCT CASE WHEN `exact` THEN `term` END) AS `exact`,
+                            -SUM(`score` * ({weight})) AS `relevance`
+                        FROM ({term_queries}) GROUP BY `key`
+                    ) AS `matches`
+                    JOIN `{table}` ON `{table}`.`key` = `matches`.`key`",
+                    table = index,
+                    weight = weight,
+                    term_queries = term_queries.join(" UNION ALL "),
+                )
+            }
+        };
+
+        if let Some(filter) = &options.filter {
+            conditions.push(filter.to_sql(index, &mut values));
+        }
+
+        Ok(Matches {
+            source,
+            conditions,
+            order,
+            values,
+        })
+    }
+}
+
+// a page of the entries matching every term of the query and the filter, in
+// ranked order, with how many there are in total
+pub fn find(
+    conn: &Connection,
+    index: &str,
+    query: &str,
+    settings: &RankSettings,
+    options: &SearchOptions,
+) -> Result<SearchResults> {
+    let matches = Matches::new(conn, index, query, settings, options)?;
+
+    // every match numbered in order, so a page can start after any of them
+    let ranked = format!(
+        "WITH `ranked` AS (
+            SELECT `{table}`.`key`, `{table}`.`data`, `{table}`.`version`, `{table}`.`modified`,
+                ROW_NUMBER() OVER (ORDER BY {order}) AS `position`
+            FROM {source}
+            {conditions}
+        )",
+        table = index,
+        source = matches.source,
+        conditions = where_clause(&matches.conditions),
+        order = matches.order.join(", ")
+    );
+
+    let mut values = matches.values.clone();
+    values.push(match &options.after {
+        Some(key) => SqlValue::Text(key.to_string()),
+        None => SqlValue::Null,
+    });
+
+    let (total, after): (usize, Option<usize>) = conn.query_row(
+        &format!(
+            "{ranked}
+            SELECT COUNT(*), (SELECT `position` FROM `ranked` WHERE `key` = ?{param}) FROM `ranked`",
+            ranked = ranked,
+            param = values.len()
+        ),
+        params_from_iter(values.iter()),
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    let start = match (&options.after, after) {
+        (Some(key), None) => {
+            return Err(Error::InvalidQuery(format!(
+                "`{}` isn't in the results, so there's nothing to continue after",
+                key
+            )))
+        }
+        (_, position) => position.unwrap_or(0),
+    };
+
+    let mut values = matches.values;
+    values.push(SqlValue::Integer(start as i64));
+    values.push(SqlValue::Integer(
+        options.limit.map_or(-1, |limit| limit as i64),
+    ));
+    values.push(SqlValue::Integer(options.offset as i64));
+
+    let mut stmt = conn.prepare(&format!(
+        "{ranked}
+        SELECT `key`, `data`, `version`, `modified` FROM `ranked`
+        WHERE `position` > ?{start}
+        ORDER BY `position`
+        LIMIT ?{limit} OFFSET ?{offset}",
+        ranked = ranked,
+        start = values.len() - 2,
+        limit = values.len() - 1,
+        offset = values.len()
+    ))?;
+
+    let rows = stmt.query_map(params_from_iter(values.iter()), Hit::from_row)?;
+
+    let mut hits = Vec::new();
+
+    for row in rows {
+        hits.push(row??);
+    }
+
+    let next = match start + options.offset + hits.len() < total {
+        true => hits.last().map(|hit| hit.key.to_string()),
+        false => None,
+    };
+
+    Ok(SearchResults { hits, total, next })
+}
+
+// how many of the entries a search finds have each value of the field, most
+// common first. every item of an array counts
+pub fn facet_values(
+    conn: &Connection,
+    index: &str,
+    query: &str,
+    options: &SearchOptions,
+    field: &str,
+) -> Result<Vec<(Value, usize)>> {
+    let Matches {
+        source,
+        mut conditions,
+        mut values,
+        ..
+    } = Matches::new(
+        conn,
+        index,
+        query,
+        &RankSettings::default(),
+        // every match is counted in no order, so only the options that page
+        // or order the hits are left out. the params of sorts would go unused
+        &SearchOptions {
+            sort: vec![],
+            limit: None,
+            offset: 0,
+            after: None,
+            ..options.clone()
+        },
+    )?;
+
+    values.push(SqlValue::Text(json_path(field)));
+    conditions.push(String::from(
+        "`facet`.`type` NOT IN ('null', 'object', 'array')",
+    ));
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT `facet`.`type`, `facet`.`value`, COUNT(DISTINCT `{table}`.`key`) AS `count`
+        FROM {source}, json_each(`{table}`.`data`, ?{param}) AS `facet`
+        {conditions}
+        GROUP BY `facet`.`type`, `facet`.`value`
+        ORDER BY `count` DESC, `facet`.`value`",
+        table = index,
+        source = source,
+        param = values.len(),
+        conditions = where_clause(&conditions)
+    ))?;
+
+    let rows = stmt.query_map(params_from_iter(values.iter()), |row| {
+        let value = match row.get::<_, String>(0)?.as_str() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            "integer" => Value::from(row.get::<_, i64>(1)?),
+            "real" => Value::from(row.get::<_, f64>(1)?),
+            _ => Value::String(row.get(1)?),
+        };
+
+        Ok((value, row.get(2)?))
+    })?;
+
+    let mut counts = Vec::new();
+
+    for row in rows {
+        counts.push(row?);
+    }
+
+    Ok(counts)
+}
+
+// the words of the term dictionary that start with `prefix`, those in the
+// most entries first
+pub fn completions(conn: &Connection, index: &str, prefix: &str) -> Result<Vec<String>> {
+    let prefix = prefix.to_lowercase();
+    let mut stmt = conn.prepare_cached(&format!(
+        "SELECT `term` FROM `{table}` WHERE `term` >= ?1 AND `term` < ?2 ORDER BY `doc` DESC, `term`",
+        table = terms_table_name(index)
+    ))?;
+
+    // the last code point sorts after anything else that starts with the prefix
+    let rows = stmt.query_map([&prefix, &format!("{}{}", prefix, char::MAX)], |row| {
+        row.get(0)
+    })?;
+
+    let mut words = Vec::new();
+
+    for row in rows {
+        words.push(row?);
+    }
+
+    Ok(words)
+}
+
+// a phrase for fts5 to match as it is
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+fn where_clause(conditions: &[String]) -> String {
+    match conditions.is_empty() {
+        true => String::new(),
+        false => format!("WHERE {}", conditions.join(" AND ")),
+    }
+}
+
+// quotes every segment so field names like `in-stock` are valid json paths
+pub fn json_path(field: &str) -> String {
+    field.split('.').fold(String::from("$"), |path, segment| {
+        format!("{}.\"{}\"", path, segment)
+    })
+}
+
+// flattens a json document into (field, text) pairs, nested objects use
+// dotted paths and every string inside an array is indexed under its field
+pub fn document_fields(document: &Value) -> Vec<(String, String)> {
+    let mut fields = Vec::new();
+
+    collect_fields(document, "", &mut fields);
+
+    fields
+}
+
+fn collect_fields(value: &Value, path: &str, fields: &mut Vec<(String, String)>) {
+    match value {
+        Value::String(text) if !path.is_empty() => fields.push((path.to_string(), text.clone())),
+        Value::Array(items) => {
+            for item in items {
+                collect_fields(item, path, fields);
+            }
+        }
+        Value::Object(map) => {
+            for (name, item) in map {
+                let path = match path.is_empty() {
+                    true => name.to_string(),
+                    false => format!("{}.{}", path, name),
+                };
+
+                collect_fields(item, &path, fields);
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // a search table with its term dictionary over these documents
+    fn indexed(documents: &[Value]) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_table(&conn, "shoes").unwrap();
+
+        for (i, document) in documents.iter().enumerate() {
+            let rowid = i as i64 + 1;
+            let schema = Schema::default();
+            insert_entry(&conn, "shoes", rowid, &rowid.to_string(), document, &schema).unwrap();
+        }
+
+        conn
+    }
+
+    #[test]
+    fn completions_are_the_words_in_the_prefix_range_most_used_first() {
+        let conn = indexed(&[
+            json!({"name": "shoe shoes"}),
+            json!({"name": "shoes shop", "brand": {"name": "shp"}}),
+            json!({"name": "shoelace sh si z𠀀 zz"}),
+        ]);
+        let completed = |prefix: &str| completions(&conn, "shoes", prefix).unwrap();
+
+        assert_eq!(completed("sho"), ["shoes", "shoe", "shoelace", "shop"]);
+        assert_eq!(completed("SHO"), completed("sho"));
+        assert_eq!(completed("shoes"), ["shoes"]);
+        assert_eq!(completed("sh")[..2], ["shoes", "sh"]);
+        assert!(completed("shoesx").is_empty());
+
+        // words past every ascii letter are still before the end of the range
+        assert_eq!(completed("z"), ["zz", "z𠀀"]);
+        assert_eq!(completed("z𠀀"), ["z𠀀"]);
+
+        let every = completed("");
+        assert_eq!(every.len(), 9);
+        assert_eq!(every[0], "shoes");
+    }
+}
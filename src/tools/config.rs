@@ -0,0 +1,196 @@
+use anyhow::{bail, Context, Result};
+use rusty_search::Engine;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+#[derive(Display, EnumString, EnumIter, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[strum(serialize_all = "lowercase")]
+pub enum Setting {
+    #[strum(ascii_case_insensitive)]
+    Db,
+    #[strum(ascii_case_insensitive)]
+    Output,
+    #[strum(ascii_case_insensitive)]
+    Address,
+}
+
+#[derive(Display, EnumString, EnumIter, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum Output {
+    #[strum(ascii_case_insensitive)]
+    Text,
+    #[strum(ascii_case_insensitive)]
+    Json,
+    #[strum(ascii_case_insensitive)]
+    Ndjson,
+}
+
+impl Setting {
+    pub fn default_value(&self) -> &'static str {
+        match self {
+            Setting::Db => "db.db",
+            Setting::Output => "text",
+            Setting::Address => "127.0.0.1:7700",
+        }
+    }
+
+    pub fn env_var(&self) -> String {
+        format!("RUSTY_SEARCH_{}", self.to_string().to_uppercase())
+    }
+
+    pub fn validate(&self, value: &str) -> Result<()> {
+        match self {
+            Setting::Db if value.trim().is_empty() => {
+                bail!("Invalid value for db, expected a file path")
+            }
+            Setting::Output if Output::from_str(value).is_err() => bail!(format!(
+                "Invalid value for output, expected {}",
+                Output::iter()
+                    .map(|o| o.to_string())
+                    .collect::<Vec<String>>()
+                    .join("|")
+            )),
+            Setting::Address if !is_address(value) => {
+                bail!("Invalid value for address, expected host:port")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// only the `host:port` shape is checked, the host is looked up by serve so
+// loading the settings never waits on dns
+fn is_address(value: &str) -> bool {
+    match value.rsplit_once(':') {
+        Some((host, port)) => !host.trim().is_empty() && port.parse::<u16>().is_ok(),
+        None => false,
+    }
+}
+
+// the config file only holds the settings that have been set, everything
+// else falls back to the setting's default
+pub struct ConfigFile {
+    pub path: PathBuf,
+    values: BTreeMap<String, String>,
+}
+
+impl ConfigFile {
+    pub fn load() -> Result<Self> {
+        let path = ConfigFile::path()?;
+
+        let values = match path.exists() {
+            true => serde_json::from_str(&fs::read_to_string(&path)?)
+                .with_context(|| format!("Invalid config file: {}", path.display()))?,
+            false => BTreeMap::new(),
+        };
+
+        Ok(Self { path, values })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(&self.path, serde_json::to_string_pretty(&self.values)?)?;
+
+        Ok(())
+    }
+
+    pub fn get(&self, setting: Setting) -> Option<&String> {
+        self.values.get(&setting.to_string())
+    }
+
+    pub fn set(&mut self, setting: Setting, value: &str) -> Result<()> {
+        setting.validate(value)?;
+        self.values.insert(setting.to_string(), value.to_string());
+
+        Ok(())
+    }
+
+    pub fn unset(&mut self, setting: Setting) -> bool {
+        self.values.remove(&setting.to_string()).is_some()
+    }
+
+    // RUSTY_SEARCH_CONFIG, then $XDG_CONFIG_HOME, then ~/.config
+    fn path() -> Result<PathBuf> {
+        if let Some(path) = env::var_os("RUSTY_SEARCH_CONFIG") {
+            return Ok(PathBuf::from(path));
+        }
+
+        let dir = match (env::var_os("XDG_CONFIG_HOME"), env::var_os("HOME")) {
+            (Some(dir), _) if !dir.is_empty() => PathBuf::from(dir),
+            (_, Some(home)) => PathBuf::from(home).join(".config"),
+            _ => bail!("Can't find a config directory, set RUSTY_SEARCH_CONFIG to a file path"),
+        };
+
+        Ok(dir.join("rusty_search").join("config.json"))
+    }
+}
+
+pub struct Settings {
+    pub db: String,
+    pub output: Output,
+    pub address: String,
+}
+
+impl Settings {
+    // resolves every setting as flag > environment variable > config file > default,
+    // when `strict` is off a bad stored or environment value falls back to the
+    // default instead of failing, so the config command can still fix it
+    pub fn init(flags: &HashMap<Setting, String>, strict: bool) -> Result<()> {
+        let file = ConfigFile::load()?;
+
+        let resolve = |setting: Setting| -> Result<String> {
+            if let Some(value) = flags.get(&setting) {
+                setting.validate(value)?;
+
+                return Ok(value.to_string());
+            }
+
+            let value = match env::var(setting.env_var()) {
+                Ok(value) => value,
+                Err(_) => file
+                    .get(setting)
+                    .cloned()
+                    .unwrap_or_else(|| setting.default_value().to_string()),
+            };
+
+            match setting.validate(&value) {
+                Err(_) if !strict => Ok(setting.default_value().to_string()),
+                Err(err) => Err(err),
+                Ok(()) => Ok(value),
+            }
+        };
+
+        let settings = Settings {
+            db: resolve(Setting::Db)?,
+            output: Output::from_str(&resolve(Setting::Output)?)?,
+            address: resolve(Setting::Address)?,
+        };
+
+        let _ = SETTINGS.set(settings);
+
+        Ok(())
+    }
+
+    pub fn get() -> &'static Settings {
+        SETTINGS.get_or_init(|| Settings {
+            db: Setting::Db.default_value().to_string(),
+            output: Output::Text,
+            address: Setting::Address.default_value().to_string(),
+        })
+    }
+
+    pub fn engine() -> Result<Engine> {
+        Ok(Engine::open(&Settings::get().db)?)
+    }
+}
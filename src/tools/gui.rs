@@ -1,18 +1,34 @@
-use crate::traits::command::Command;
+use crate::tools::config::{Output, Settings};
+use crate::tools::report::Report;
+use crate::traits::command::{usage, ActionSpec, Command, ParamRule};
+use serde_json::Value;
 
-pub struct GUI {}
+#[allow(clippy::upper_case_acronyms)]
+pub struct GUI {
+    output: Output,
+}
 
 const TITLE_SPACING: u32 = 2;
 const SUB_TITLE_SPACING: u32 = 4;
 const CONTENT_SPACING: u32 = 6;
 
 impl GUI {
+    // text is only printed in the text output, json modes only print reports
     pub fn new() -> Self {
-        Self {}
+        Self {
+            output: Settings::get().output,
+        }
+    }
+
+    // help has no structured form so it is always printed as text
+    pub fn text() -> Self {
+        Self {
+            output: Output::Text,
+        }
     }
 
     pub fn print_params(&self, command: &dyn Command) {
-        if command.get_params().len() > 0 {
+        if !command.get_params().is_empty() {
             self.sub_title("params:");
 
             for param in command.get_params().iter() {
@@ -24,7 +40,7 @@ impl GUI {
                     "{key}:{spacing}{value}",
                     key = param.0,
                     spacing = spacing,
-                    value = param.1
+                    value = param.1.join(", ")
                 ));
             }
 
@@ -35,24 +51,122 @@ impl GUI {
     pub fn title(&self, title: &str) -> &Self {
         self.nl();
         let spacing: String = (0..TITLE_SPACING).map(|_| ' ').collect();
-        println!("{}## {} ##\n", spacing, title);
+        self.print(&format!("{}## {} ##\n", spacing, title));
         self
     }
 
     pub fn sub_title(&self, title: &str) -> &Self {
         let spacing: String = (0..SUB_TITLE_SPACING).map(|_| ' ').collect();
-        println!("{}{}", spacing, title);
+        self.print(&format!("{}{}", spacing, title));
         self
     }
 
     pub fn content(&self, title: &str) -> &Self {
         let spacing: String = (0..CONTENT_SPACING).map(|_| ' ').collect();
-        println!("{}{}", spacing, title);
+        self.print(&format!("{}{}", spacing, title));
+        self
+    }
+
+    // lines up `name: {params} | description` for each action
+    pub fn actions(&self, actions: &[ActionSpec]) -> &Self {
+        let usages: Vec<String> = actions.iter().map(|action| usage(&action.rules)).collect();
+        let name_width = actions
+            .iter()
+            .map(|action| action.name.len())
+            .max()
+            .unwrap_or(0)
+            + 2;
+        let usage_width = usages.iter().map(|usage| usage.len()).max().unwrap_or(0) + 4;
+
+        for (action, usage) in actions.iter().zip(usages) {
+            self.content(&format!(
+                "{name:<name_width$}{usage:<usage_width$}| {description}",
+                name = format!("{}:", action.name),
+                usage = usage,
+                description = action.description,
+                name_width = name_width,
+                usage_width = usage_width
+            ));
+        }
+
+        self
+    }
+
+    // one line per param with what it accepts, e.g. `{?mode|-m}  optional, default `atomic``
+    pub fn rules(&self, rules: &[ParamRule]) -> &Self {
+        let usages: Vec<String> = rules.iter().map(|rule| rule.usage()).collect();
+        let width = usages.iter().map(|usage| usage.len()).max().unwrap_or(0) + 2;
+
+        for (rule, usage) in rules.iter().zip(usages) {
+            let mut details = vec![match rule.required {
+                true => String::from("required"),
+                false => String::from("optional"),
+            }];
+
+            if rule.flag {
+                details.push(String::from("flag"));
+            }
+            if rule.repeated {
+                details.push(String::from("can be repeated"));
+            }
+            if !rule.choices.is_empty() {
+                details.push(format!("one of {}", rule.choices.join("|")));
+            }
+            if let Some(default) = rule.default {
+                details.push(format!("default `{}`", default));
+            }
+
+            self.content(&format!(
+                "{usage:<width$}{details}",
+                usage = usage,
+                details = details.join(", "),
+                width = width
+            ));
+        }
+
         self
     }
 
     pub fn nl(&self) -> &Self {
-        println!();
+        self.print("");
         self
     }
+
+    // ndjson streams the items of an array `data` one per line, then the
+    // report itself with `data` left as null
+    pub fn report(&self, report: &Report) -> &Self {
+        match self.output {
+            Output::Text => {
+                if let Some(text) = report.text() {
+                    self.sub_title("result:").content(&text).nl();
+                }
+            }
+            Output::Json => self.record(&serde_json::json!(report)),
+            Output::Ndjson => {
+                let mut report = serde_json::json!(report);
+
+                match report["data"].take() {
+                    Value::Array(items) => items.iter().for_each(|item| self.record(item)),
+                    data => report["data"] = data,
+                }
+
+                self.record(&report);
+            }
+        }
+
+        self
+    }
+
+    // a single json line, only printed by the json outputs
+    pub fn record(&self, value: &Value) {
+        if self.output != Output::Text {
+            println!("{}", value);
+        }
+    }
+
+    fn print(&self, text: &str) {
+        if self.output == Output::Text {
+            println!("{}", text);
+        }
+    }
 }
@@ -0,0 +1,132 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::sync::OnceLock;
+use strum_macros::Display;
+
+static CONTEXT: OnceLock<(String, String)> = OnceLock::new();
+
+#[derive(Serialize, Display, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Success,
+    Warning,
+    Error,
+    // the entry isn't at the version the command expected
+    Conflict,
+}
+
+// the structured result of a command, every field is always present in the
+// json output so scripts can rely on the shape
+#[derive(Serialize, Debug)]
+pub struct Report {
+    pub status: Status,
+    pub command: String,
+    pub action: String,
+    pub index: Option<String>,
+    pub key: Option<String>,
+    pub count: Option<usize>,
+    pub message: Option<String>,
+    pub error: Option<String>,
+    // the records of the result, streamed one per line by ndjson
+    pub data: Option<Value>,
+    // details about the result as a whole, like a total or the next page,
+    // kept out of `data` so its records always have the same shape
+    pub summary: Option<Value>,
+}
+
+impl Report {
+    pub fn success(message: &str) -> Self {
+        Report::new(Status::Success, Some(message))
+    }
+
+    pub fn warning(message: &str) -> Self {
+        Report::new(Status::Warning, Some(message))
+    }
+
+    pub fn error(error: &str) -> Self {
+        let mut report = Report::new(Status::Error, None);
+        report.error = Some(error.to_string());
+        report
+    }
+
+    pub fn conflict(error: &str) -> Self {
+        let mut report = Report::new(Status::Conflict, None);
+        report.error = Some(error.to_string());
+        report
+    }
+
+    // a successful result whose text form is printed by the command itself
+    pub fn data(data: Value) -> Self {
+        Report::new(Status::Success, None).with_data(data)
+    }
+
+    pub fn index(mut self, index: &str) -> Self {
+        self.index = Some(index.to_string());
+        self
+    }
+
+    pub fn key(mut self, key: &str) -> Self {
+        self.key = Some(key.to_string());
+        self
+    }
+
+    pub fn count(mut self, count: usize) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    pub fn with_summary(mut self, summary: Value) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    // the text shown for the result in the default output
+    pub fn text(&self) -> Option<String> {
+        match (&self.message, &self.error) {
+            (_, Some(error)) => Some(format!("{}: {}", self.status, error)),
+            (Some(message), _) => Some(format!("{}: {}", self.status, message)),
+            _ => None,
+        }
+    }
+
+    // set once by main so every report names the command that produced it
+    pub fn set_context(command: &str, action: &str) {
+        let _ = CONTEXT.set((command.to_lowercase(), action.to_lowercase()));
+    }
+
+    fn new(status: Status, message: Option<&str>) -> Self {
+        let (command, action) = CONTEXT.get().cloned().unwrap_or_default();
+
+        Self {
+            status,
+            command,
+            action,
+            index: None,
+            key: None,
+            count: None,
+            message: message.map(|message| message.to_string()),
+            error: None,
+            data: None,
+            summary: None,
+        }
+    }
+}
+
+// returned by a command that has already reported its error, main only has
+// to exit with a failure
+#[derive(Debug)]
+pub struct Reported;
+
+impl Display for Reported {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "the error has been reported")
+    }
+}
+
+impl std::error::Error for Reported {}
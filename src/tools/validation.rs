@@ -1,24 +1,46 @@
 use anyhow::{bail, Result};
 
 use crate::traits::command::ParamRule;
+use rusty_search::{is_field_name, parse_sort, Filter};
 
 pub enum StringValidation {
     SqlTable,
-    SqlColumn,
     Bool,
+    Json,
+    // json that has to be an object, like a merge patch of fields
+    JsonObject,
+    Field,
+    // a comma separated list of field names
+    Fields,
+    // an expression like `price < 50 AND in_stock = true`
+    Filter,
+    // sorts like `price:asc,rating:desc`
+    Sort,
+    Number,
+    Count,
+    // a count that can also be 0
+    Offset,
     Ignore,
 }
 
-pub fn validate_string(value: &String, rule: &ParamRule) -> Result<()> {
+pub fn validate_string(value: &str, rule: &ParamRule) -> Result<()> {
     match rule.validation {
         StringValidation::SqlTable => sql_table(value),
-        StringValidation::SqlColumn => sql_column(value),
         StringValidation::Bool => boolean(value, rule.key),
+        StringValidation::Json => json(value, rule.key),
+        StringValidation::JsonObject => json_object(value, rule.key),
+        StringValidation::Field => field(value),
+        StringValidation::Fields => value.split(',').try_for_each(field),
+        StringValidation::Filter => filter(value),
+        StringValidation::Sort => sort(value),
+        StringValidation::Number => number(value, rule.key),
+        StringValidation::Count => count(value, rule.key),
+        StringValidation::Offset => offset(value, rule.key),
         StringValidation::Ignore => Ok(()),
     }
 }
 
-pub fn sql_table(name: &String) -> Result<()> {
+pub fn sql_table(name: &str) -> Result<()> {
     for char in name.chars() {
         if !char.is_alphabetic() {
             bail!("Invalid value for index, expected alphabetic string");
@@ -27,19 +49,72 @@ pub fn sql_table(name: &String) -> Result<()> {
     Ok(())
 }
 
-pub fn sql_column(name: &String) -> Result<()> {
-    for char in name.chars() {
-        if !char.is_alphabetic() {
-            bail!("Invalid value for key, expected alphabetic string");
+pub fn boolean(name: &str, key: &str) -> Result<()> {
+    match name {
+        "true" | "false" => Ok(()),
+        _ if name == key => Ok(()),
+        _ => bail!("Invalid value for boolean, expected true|false"),
+    }
+}
+
+pub fn json(value: &str, key: &str) -> Result<()> {
+    match serde_json::from_str::<serde_json::Value>(value) {
+        Ok(_) => Ok(()),
+        Err(err) => bail!(format!("Invalid value for {}, expected json: {}", key, err)),
+    }
+}
+
+pub fn json_object(value: &str, key: &str) -> Result<()> {
+    json(value, key)?;
+
+    match serde_json::from_str::<serde_json::Value>(value)?.is_object() {
+        true => Ok(()),
+        false => bail!(format!("Invalid value for {}, expected a json object", key)),
+    }
+}
+
+pub fn field(name: &str) -> Result<()> {
+    match is_field_name(name) {
+        true => Ok(()),
+        false => {
+            bail!("Invalid value for field, expected a field name like `brand` or `brand.name`")
         }
     }
+}
+
+pub fn filter(value: &str) -> Result<()> {
+    Filter::parse(value)?;
     Ok(())
 }
 
-pub fn boolean(name: &String, key: &str) -> Result<()> {
-    match name.as_str() {
-        "true" | "false" => Ok(()),
-        _ if name == key => Ok(()),
-        _ => bail!("Invalid value for boolean, expected true|false"),
+pub fn sort(value: &str) -> Result<()> {
+    parse_sort(value)?;
+    Ok(())
+}
+
+pub fn number(value: &str, key: &str) -> Result<()> {
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() => Ok(()),
+        _ => bail!(format!("Invalid value for {}, expected a number", key)),
+    }
+}
+
+pub fn count(value: &str, key: &str) -> Result<()> {
+    match value.parse::<usize>() {
+        Ok(count) if count > 0 => Ok(()),
+        _ => bail!(format!(
+            "Invalid value for {}, expected a whole number above 0",
+            key
+        )),
+    }
+}
+
+pub fn offset(value: &str, key: &str) -> Result<()> {
+    match value.parse::<usize>() {
+        Ok(_) => Ok(()),
+        _ => bail!(format!(
+            "Invalid value for {}, expected a whole number",
+            key
+        )),
     }
 }
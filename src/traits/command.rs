@@ -1,19 +1,24 @@
+use crate::tools::gui::GUI;
 use crate::tools::validation::{validate_string, StringValidation};
 use anyhow::{bail, Result};
 use std::collections::HashMap;
+use std::fmt;
+
+// every param keeps a list of values, only repeated params hold more than one
+pub type Params = HashMap<String, Vec<String>>;
 
 #[macro_export]
 macro_rules! derive_getters {
     () => {
-        fn set_params(&mut self, params: HashMap<String, String>) {
+        fn set_params(&mut self, params: Params) {
             self.params = params;
         }
 
-        fn get_params(&self) -> &HashMap<String, String> {
+        fn get_params(&self) -> &Params {
             &self.params
         }
 
-        fn get_params_mut(&mut self) -> &mut HashMap<String, String> {
+        fn get_params_mut(&mut self) -> &mut Params {
             &mut self.params
         }
     };
@@ -23,49 +28,458 @@ pub struct ParamRule<'a> {
     pub key: &'a str,
     pub validation: StringValidation,
     pub required: &'a bool,
+    // single letter alias, given as `-c`
+    pub short: Option<char>,
+    // a bool that is true when given as `--key` without a value
+    pub flag: bool,
+    pub default: Option<&'a str>,
+    // can be given more than once, a repeated param with only optional params
+    // after it takes every remaining positional value
+    pub repeated: bool,
+    // the only values accepted, also offered as shell completions
+    pub choices: &'a [&'a str],
+}
+
+// an action of a command, its help, usage, param errors and completions are
+// all generated from this
+pub struct ActionSpec {
+    pub name: &'static str,
+    pub rules: Vec<ParamRule<'static>>,
+    pub description: &'static str,
+}
+
+// a param that is missing, unknown or given too often, it gets the usage
+// of its action added before it's shown
+#[derive(Debug)]
+pub struct ParamError(String);
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParamError {}
+
+impl Default for ParamRule<'_> {
+    fn default() -> Self {
+        Self {
+            key: "",
+            validation: StringValidation::Ignore,
+            required: &false,
+            short: None,
+            flag: false,
+            default: None,
+            repeated: false,
+            choices: &[],
+        }
+    }
+}
+
+impl ParamRule<'_> {
+    // how the param is shown in help, e.g. `{index}`, `{?format|-f}` or `{keys...}`
+    pub fn usage(&self) -> String {
+        let mut usage = String::from("{");
+
+        if !*self.required {
+            usage.push('?');
+        }
+        if self.flag {
+            usage.push_str("--");
+        }
+
+        usage.push_str(self.key);
+
+        if self.repeated {
+            usage.push_str("...");
+        }
+        if let Some(short) = self.short {
+            usage.push_str(&format!("|-{}", short));
+        }
+
+        usage.push('}');
+        usage
+    }
+}
+
+pub fn usage(rules: &[ParamRule]) -> String {
+    rules
+        .iter()
+        .map(|rule| rule.usage())
+        .collect::<Vec<String>>()
+        .join(" ")
 }
 
 pub trait Command: Runnable {
-    fn assert_params(
-        &mut self,
-        rules: Vec<ParamRule>,
-        params: &[String],
-    ) -> Result<&HashMap<String, String>> {
+    // params can be given by position, as `--key value`, `--key=value` or
+    // `-k value`, and flags as `--key`. everything after `--` is positional
+    fn assert_params(&mut self, rules: Vec<ParamRule>, params: &[String]) -> Result<&Params> {
+        let mut named: Params = HashMap::new();
+        let mut positional = Vec::new();
+        let mut args = params.iter();
+
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                positional.extend(args.by_ref().cloned());
+                break;
+            }
+
+            let (name, value) = match named_arg(arg) {
+                Some(named) => named,
+                None => {
+                    positional.push(arg.to_string());
+                    continue;
+                }
+            };
+
+            let rule = match rules.iter().find(|rule| match name {
+                Name::Long(key) => rule.key == key,
+                Name::Short(short) => rule.short == Some(short),
+            }) {
+                Some(rule) => rule,
+                None => bail!(ParamError(format!("Unknown param: {}", arg))),
+            };
+
+            let value = match (value, rule.flag) {
+                (Some(value), _) => value.to_string(),
+                (None, true) => String::from("true"),
+                (None, false) => match args.next() {
+                    Some(value) => value.to_string(),
+                    None => bail!(ParamError(format!(
+                        "No value entered for the param: {}",
+                        rule.key
+                    ))),
+                },
+            };
+
+            let values = named.entry(rule.key.to_string()).or_default();
+
+            if !values.is_empty() && !rule.repeated {
+                bail!(ParamError(format!(
+                    "The param {} can only be given once",
+                    rule.key
+                )));
+            }
+
+            values.push(value);
+        }
+
+        // positional values fill the params that weren't named, in order. a
+        // repeated param takes the rest when everything after it is optional,
+        // which then has to be named
+        let mut positional = positional.into_iter().peekable();
+
         for (i, rule) in rules.iter().enumerate() {
-            if let Some(value) = params.get(i) {
-                validate_string(value, &rule)?;
+            if named.contains_key(rule.key) {
+                continue;
+            }
+
+            let takes_rest = rule.repeated && rules[i + 1..].iter().all(|rule| !*rule.required);
+            let mut values = Vec::new();
+
+            while let Some(value) = positional.next_if(|_| values.is_empty() || takes_rest) {
+                values.push(value);
+            }
 
-                self.get_params_mut()
-                    .insert(rule.key.to_string(), value.to_string());
-            } else if *rule.required {
-                bail!(format!("No value entered for the param: {}", rule.key));
+            if !values.is_empty() {
+                named.insert(rule.key.to_string(), values);
             }
         }
 
+        if let Some(value) = positional.next() {
+            bail!(ParamError(format!("Unexpected param: {}", value)));
+        }
+
+        for rule in rules.iter() {
+            let values = match (named.remove(rule.key), rule.default) {
+                (Some(values), _) => values,
+                (None, Some(default)) => vec![default.to_string()],
+                (None, None) if *rule.required => bail!(ParamError(format!(
+                    "No value entered for the param: {}",
+                    rule.key
+                ))),
+                (None, None) => continue,
+            };
+
+            for value in values.iter() {
+                validate_string(value, rule)?;
+
+                if !rule.choices.is_empty()
+                    && !rule
+                        .choices
+                        .iter()
+                        .any(|choice| choice.eq_ignore_ascii_case(value))
+                {
+                    bail!(format!(
+                        "Invalid value for {}, expected {}",
+                        rule.key,
+                        rule.choices.join("|")
+                    ));
+                }
+            }
+
+            self.get_params_mut().insert(rule.key.to_string(), values);
+        }
+
         Ok(self.get_params())
     }
     fn get_param(&self, key: &str) -> &str {
+        self.get_param_opt(key)
+            .unwrap_or_else(|| panic!("Can't find expected param: {}", key))
+    }
+    fn get_param_opt(&self, key: &str) -> Option<&str> {
         self.get_params()
             .get(key)
-            .expect(&format!("Can't find expected param: {}", key))
+            .and_then(|values| values.first())
+            .map(|value| value.as_str())
     }
-    fn get_param_bool(&self, key: &str) -> bool {
+    fn get_param_values(&self, key: &str) -> &[String] {
         match self.get_params().get(key) {
+            Some(values) => values,
+            None => &[],
+        }
+    }
+    fn get_param_bool(&self, key: &str) -> bool {
+        match self.get_param_opt(key) {
             Some(value) => value == "true" || value == key,
             None => false,
         }
     }
-    fn set_params(&mut self, args: HashMap<String, String>);
-    fn get_params(&self) -> &HashMap<String, String>;
-    fn get_params_mut(&mut self) -> &mut HashMap<String, String>;
+    fn set_params(&mut self, args: Params);
+    fn get_params(&self) -> &Params;
+    fn get_params_mut(&mut self) -> &mut Params;
     fn help(&self) -> Result<()>;
-    fn print_title(&self) -> bool {
+    fn actions(&self) -> Vec<ActionSpec> {
+        vec![]
+    }
+    fn action(&self, name: &str) -> Option<ActionSpec> {
+        self.actions()
+            .into_iter()
+            .find(|action| action.name.eq_ignore_ascii_case(name))
+    }
+    // `{command} {action} --help`, unknown actions fall back to the command help
+    fn action_help(&self, command: &str, name: &str) -> Result<()> {
+        let action = match self.action(name) {
+            Some(action) => action,
+            None => return self.help(),
+        };
+
+        GUI::text()
+            .title(&format!("{} {}", command, action.name))
+            .sub_title("usage:")
+            .nl()
+            .content(&format!(
+                "{} {} {}",
+                command,
+                action.name,
+                usage(&action.rules)
+            ))
+            .nl()
+            .content(action.description)
+            .nl()
+            .sub_title("params:")
+            .nl()
+            .rules(&action.rules)
+            .nl();
+
+        Ok(())
+    }
+    fn print_title(&self, _action: &str, _params: &[String]) -> bool {
         true
     }
 }
 
 pub trait Runnable {
-    fn run(&mut self, action: &String, params: &[String]) -> Result<()>;
+    fn run(&mut self, action: &str, params: &[String]) -> Result<()>;
+}
+
+pub enum Name<'a> {
+    Long(&'a str),
+    Short(char),
+}
+
+// `--key`, `--key=value` or `-k`, anything else like `-` or `-1.5` is a value
+pub fn named_arg(arg: &str) -> Option<(Name<'_>, Option<&str>)> {
+    if let Some(flag) = arg.strip_prefix("--") {
+        return match flag.split_once('=') {
+            Some((key, value)) => Some((Name::Long(key), Some(value))),
+            None if !flag.is_empty() => Some((Name::Long(flag), None)),
+            None => None,
+        };
+    }
+
+    let mut chars = arg.strip_prefix('-')?.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(short), None) if short.is_ascii_alphabetic() => Some((Name::Short(short), None)),
+        _ => None,
+    }
 }
 
 pub(crate) use derive_getters;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::validation::StringValidation::{Count, SqlTable};
+
+    struct Test {
+        params: Params,
+    }
+
+    impl Command for Test {
+        derive_getters!();
+
+        fn help(&self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Runnable for Test {
+        fn run(&mut self, _action: &str, _params: &[String]) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn rules() -> Vec<ParamRule<'static>> {
+        vec![
+            ParamRule {
+                key: "index",
+                validation: SqlTable,
+                required: &true,
+                ..Default::default()
+            },
+            ParamRule {
+                key: "keys",
+                required: &true,
+                repeated: true,
+                ..Default::default()
+            },
+            ParamRule {
+                key: "format",
+                short: Some('f'),
+                default: Some("json"),
+                choices: &["json", "csv"],
+                ..Default::default()
+            },
+            ParamRule {
+                key: "limit",
+                validation: Count,
+                ..Default::default()
+            },
+            ParamRule {
+                key: "compact",
+                short: Some('c'),
+                flag: true,
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn parse(params: &[&str]) -> Result<Params> {
+        let params: Vec<String> = params.iter().map(|param| param.to_string()).collect();
+        let mut test = Test {
+            params: HashMap::new(),
+        };
+
+        Ok(test.assert_params(rules(), &params)?.clone())
+    }
+
+    // the message of a param error, which gets the usage added
+    fn param_error(params: &[&str]) -> String {
+        let err = parse(params).unwrap_err();
+        assert!(err.is::<ParamError>(), "{} isn't a param error", err);
+
+        err.to_string()
+    }
+
+    #[test]
+    fn positional_values_fill_params_in_order() {
+        let params = parse(&["shoes", "a", "b"]).unwrap();
+
+        assert_eq!(params["index"], ["shoes"]);
+        assert_eq!(params["keys"], ["a", "b"]);
+        assert_eq!(params["format"], ["json"]);
+        assert!(!params.contains_key("limit"));
+        assert!(!params.contains_key("compact"));
+    }
+
+    #[test]
+    fn params_can_be_named_long_short_or_with_an_equals_sign() {
+        let params = parse(&[
+            "--index=shoes",
+            "--keys",
+            "a",
+            "-f",
+            "csv",
+            "--limit",
+            "5",
+            "-c",
+        ])
+        .unwrap();
+
+        assert_eq!(params["index"], ["shoes"]);
+        assert_eq!(params["keys"], ["a"]);
+        assert_eq!(params["format"], ["csv"]);
+        assert_eq!(params["limit"], ["5"]);
+        assert_eq!(params["compact"], ["true"]);
+
+        let params = parse(&["shoes", "a", "--compact=false", "--format=CSV"]).unwrap();
+
+        assert_eq!(params["compact"], ["false"]);
+        assert_eq!(params["format"], ["CSV"]);
+    }
+
+    #[test]
+    fn repeated_params_take_every_value() {
+        let params = parse(&["shoes", "--keys", "a", "-c", "--keys", "b"]).unwrap();
+        assert_eq!(params["keys"], ["a", "b"]);
+
+        let params = parse(&["shoes", "a", "-1.5", "-", "--", "--compact"]).unwrap();
+        assert_eq!(params["keys"], ["a", "-1.5", "-", "--compact"]);
+        assert!(!params.contains_key("compact"));
+
+        assert_eq!(
+            param_error(&["shoes", "a", "-f", "csv", "--format", "json"]),
+            "The param format can only be given once"
+        );
+    }
+
+    #[test]
+    fn required_params_have_to_be_given() {
+        assert_eq!(param_error(&[]), "No value entered for the param: index");
+        assert_eq!(
+            param_error(&["shoes"]),
+            "No value entered for the param: keys"
+        );
+        assert_eq!(
+            param_error(&["shoes", "a", "--limit"]),
+            "No value entered for the param: limit"
+        );
+    }
+
+    #[test]
+    fn unknown_params_are_rejected() {
+        assert_eq!(
+            param_error(&["shoes", "a", "--nope"]),
+            "Unknown param: --nope"
+        );
+        assert_eq!(param_error(&["shoes", "a", "-x"]), "Unknown param: -x");
+        assert_eq!(
+            param_error(&["shoes", "a", "--nope=1"]),
+            "Unknown param: --nope=1"
+        );
+    }
+
+    #[test]
+    fn values_are_validated() {
+        let err = parse(&["shoes", "a", "-f", "xml"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid value for format, expected json|csv"
+        );
+
+        assert!(parse(&["shoes", "a", "--limit", "0"]).is_err());
+        assert!(parse(&["not a table", "a"]).is_err());
+    }
+}
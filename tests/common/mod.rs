@@ -0,0 +1,62 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::{env, fs, process};
+
+// a config file and database of its own for each test, removed afterwards
+pub struct Sandbox {
+    pub dir: PathBuf,
+}
+
+#[allow(dead_code)]
+impl Sandbox {
+    pub fn new(name: &str) -> Self {
+        let dir = env::temp_dir().join(format!("rusty_search_{}_{}", name, process::id()));
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        Self { dir }
+    }
+
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_rusty_search"));
+
+        command
+            .env("RUSTY_SEARCH_CONFIG", self.dir.join("config.json"))
+            .env("RUSTY_SEARCH_DB", self.dir.join("test.db"));
+
+        command
+    }
+
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.command().args(args).output().unwrap()
+    }
+
+    // fails the test with the output when the command fails
+    pub fn ok(&self, args: &[&str]) -> String {
+        let output = self.run(args);
+
+        assert!(
+            output.status.success(),
+            "{:?} failed: {}{}",
+            args,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    pub fn write(&self, name: &str, contents: &str) -> String {
+        let path = self.dir.join(name);
+        fs::write(&path, contents).unwrap();
+
+        path.to_string_lossy().to_string()
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::Sandbox;
+
+// the candidates offered for the last word
+fn candidates(sandbox: &Sandbox, words: &[&str]) -> Vec<String> {
+    let mut args = vec!["completions", "complete", "--"];
+    args.extend(words);
+
+    sandbox.ok(&args).lines().map(String::from).collect()
+}
+
+#[test]
+fn commands_actions_and_params_are_offered_in_turn() {
+    let sandbox = Sandbox::new("completions_words");
+
+    let commands = candidates(&sandbox, &[""]);
+    assert!(commands.contains(&"manage".to_string()));
+    assert!(commands.contains(&"completions".to_string()));
+
+    assert_eq!(candidates(&sandbox, &["se"]), ["search", "serve"]);
+    assert_eq!(
+        candidates(&sandbox, &["--output", ""]),
+        ["text", "json", "ndjson"]
+    );
+    assert!(candidates(&sandbox, &["-"]).contains(&"--db".to_string()));
+
+    let actions = candidates(&sandbox, &["manage", ""]);
+    assert!(actions.contains(&"info".to_string()));
+    assert!(actions.contains(&"help".to_string()));
+
+    assert_eq!(
+        candidates(
+            &sandbox,
+            &["search", "query", "shoes", "shoes", "--metric", ""]
+        ),
+        ["damerau", "levenshtein"]
+    );
+    assert!(candidates(&sandbox, &["nope", ""]).is_empty());
+}
+
+#[test]
+fn index_names_come_from_the_database_in_use() {
+    let sandbox = Sandbox::new("completions_indexes");
+    let other = sandbox.dir.join("other.db");
+    let other = other.to_str().unwrap();
+
+    // nothing is offered, and nothing created, without a database
+    assert!(candidates(&sandbox, &["manage", "info", ""]).is_empty());
+    assert!(!sandbox.dir.join("test.db").exists());
+
+    sandbox.ok(&["manage", "create", "shoes"]);
+    sandbox.ok(&["manage", "create", "shirts"]);
+    sandbox.ok(&["manage", "create", "hats", "--db", other]);
+
+    assert_eq!(
+        candidates(&sandbox, &["manage", "info", ""]),
+        ["shirts", "shoes"]
+    );
+    assert_eq!(candidates(&sandbox, &["manage", "info", "sho"]), ["shoes"]);
+    assert_eq!(
+        candidates(&sandbox, &["--db", other, "manage", "info", ""]),
+        ["hats"]
+    );
+}
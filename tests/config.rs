@@ -0,0 +1,85 @@
+mod common;
+
+use common::Sandbox;
+
+#[test]
+fn addresses_are_only_checked_for_their_shape() {
+    let sandbox = Sandbox::new("config_address");
+
+    sandbox.ok(&["config", "set", "address", "nosuchhost.invalid:7700"]);
+    sandbox.ok(&["manage", "list"]);
+
+    let output = sandbox.run(&["serve", "start"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Can't resolve address"));
+
+    for address in ["nosuchhost", ":7700", "localhost:port", "localhost:70000"] {
+        let output = sandbox.run(&["config", "set", "address", address]);
+        assert!(!output.status.success(), "{} was accepted", address);
+    }
+}
+
+#[test]
+fn settings_come_from_flags_then_env_then_file_then_default() {
+    let sandbox = Sandbox::new("config_precedence");
+
+    // the index is made in whichever database the db setting resolved to
+    let create = |index: &str, env: bool, flag: bool| {
+        let mut command = sandbox.command();
+        command
+            .current_dir(&sandbox.dir)
+            .args(["manage", "create", index]);
+
+        if !env {
+            command.env_remove("RUSTY_SEARCH_DB");
+        }
+        if flag {
+            command.args(["--db", "flag.db"]);
+        }
+
+        assert!(command.output().unwrap().status.success());
+    };
+
+    create("a", false, false);
+    assert!(sandbox.dir.join("db.db").exists());
+
+    sandbox.ok(&["config", "set", "db", "file.db"]);
+    create("b", false, false);
+    assert!(sandbox.dir.join("file.db").exists());
+
+    create("c", true, false);
+    assert!(sandbox.dir.join("test.db").exists());
+
+    create("d", true, true);
+    assert!(sandbox.dir.join("flag.db").exists());
+
+    let output = sandbox.ok(&["config", "get", "db"]);
+    assert!(output.contains("RUSTY_SEARCH_DB"), "{}", output);
+}
+
+#[test]
+fn bad_settings_only_stop_commands_other_than_config() {
+    let sandbox = Sandbox::new("config_bad");
+    sandbox.write("config.json", r#"{"output": "yaml"}"#);
+
+    let output = sandbox.run(&["manage", "list"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid value for output"));
+
+    let output = sandbox.ok(&["config", "get", "output"]);
+    assert!(output.contains("output: yaml (config file)"), "{}", output);
+
+    sandbox.ok(&["config", "unset", "output"]);
+    sandbox.ok(&["manage", "list"]);
+
+    let output = sandbox
+        .command()
+        .env("RUSTY_SEARCH_ADDRESS", "nowhere")
+        .args(["config", "set", "output", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let output = sandbox.run(&["config", "list", "--output", "yaml"]);
+    assert!(!output.status.success());
+}
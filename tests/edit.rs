@@ -0,0 +1,299 @@
+mod common;
+
+use common::Sandbox;
+use rusty_search::{Engine, Error, KeyCheck, PatchFormat, SearchOptions};
+use serde_json::{json, Value};
+use std::env;
+use std::fs;
+use std::process;
+
+#[test]
+fn updates_by_query_have_to_be_objects() {
+    let engine = Engine::open_in_memory().unwrap();
+    let index = engine.create_index("shoes").unwrap();
+
+    index
+        .add_document("a", &json!({"title": "red shoes"}))
+        .unwrap();
+
+    for patch in [json!(null), json!([1]), json!("shoes")] {
+        assert!(matches!(
+            index.update_by_query("red", &SearchOptions::default(), &patch),
+            Err(Error::InvalidPatch(_))
+        ));
+    }
+
+    let sandbox = Sandbox::new("update_by_query_object");
+    sandbox.ok(&[
+        "manage",
+        "init",
+        "shoes",
+        "sku",
+        r#"[{"sku": "a", "title": "red shoes"}]"#,
+    ]);
+
+    for patch in ["null", "[1]", "\"shoes\""] {
+        let output = sandbox.run(&["edit", "update-by-query", "shoes", patch, "red", "--apply"]);
+
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("expected a json object"));
+    }
+
+    sandbox.ok(&[
+        "edit",
+        "update-by-query",
+        "shoes",
+        r#"{"sale": true}"#,
+        "red",
+        "--apply",
+    ]);
+}
+
+#[test]
+fn entries_stay_as_they_were_when_their_search_rows_fail() {
+    let path = env::temp_dir().join(format!("rusty_search_search_rows_{}.db", process::id()));
+    let _ = fs::remove_file(&path);
+
+    let engine = Engine::open(&path).unwrap();
+    let index = engine.create_index("shoes").unwrap();
+
+    index
+        .add_document("a", &json!({"title": "red shoes"}))
+        .unwrap();
+
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .execute_batch("DROP TABLE `shoes_search`")
+        .unwrap();
+
+    assert!(index
+        .update_document("a", &json!({"title": "blue shoes"}), None)
+        .is_err());
+    assert!(index
+        .add_document("b", &json!({"title": "blue shoes"}))
+        .is_err());
+
+    let hit = index.get_document("a").unwrap().unwrap();
+
+    assert_eq!(hit.document, json!({"title": "red shoes"}));
+    assert_eq!(hit.version, 1);
+    assert!(index.get_document("b").unwrap().is_none());
+
+    assert!(index.remove_document("a", None).is_err());
+    assert!(index.get_document("a").unwrap().is_some());
+
+    drop(engine);
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn adding_an_existing_key_fails() {
+    let sandbox = Sandbox::new("add_existing");
+    sandbox.ok(&["manage", "create", "shoes"]);
+    sandbox.ok(&["edit", "add", "shoes", "a", r#"{"title": "red shoes"}"#]);
+
+    let output = sandbox.run(&[
+        "--output",
+        "json",
+        "edit",
+        "add",
+        "shoes",
+        "a",
+        r#"{"title": "blue shoes"}"#,
+    ]);
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert!(!output.status.success());
+    assert_eq!(report["status"], "error");
+    assert_eq!(report["error"], "Entry already exists for shoes->a");
+
+    let output = sandbox.ok(&["--output", "json", "edit", "get", "shoes", "a"]);
+    let report: Value = serde_json::from_str(&output).unwrap();
+
+    assert_eq!(report["data"]["data"]["title"], "red shoes");
+}
+
+#[test]
+fn getting_a_missing_key_fails() {
+    let sandbox = Sandbox::new("get_missing");
+    sandbox.ok(&["manage", "create", "shoes"]);
+
+    let output = sandbox.run(&["--output", "json", "edit", "get", "shoes", "a"]);
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert!(!output.status.success());
+    assert_eq!(report["status"], "error");
+    assert_eq!(report["key"], "a");
+    assert_eq!(report["error"], "No matching entry for shoes->a");
+
+    let output = sandbox.run(&["edit", "get", "shoes", "a"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No matching entry for shoes->a"));
+}
+
+#[test]
+fn if_version_only_changes_the_entry_at_that_version() {
+    let sandbox = Sandbox::new("if_version");
+    sandbox.ok(&["manage", "create", "shoes"]);
+    sandbox.ok(&["edit", "add", "shoes", "a", r#"{"price": 30}"#]);
+
+    let version = |sandbox: &Sandbox| -> Value {
+        let output = sandbox.ok(&["--output", "json", "edit", "get", "shoes", "a"]);
+        serde_json::from_str::<Value>(&output).unwrap()["data"]["version"].clone()
+    };
+    let conflict = |args: &[&str]| {
+        let output = sandbox.run(&[&["--output", "json", "edit"], args].concat());
+        let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+        assert_eq!(output.status.code(), Some(3), "{:?}", args);
+        assert_eq!(report["status"], "conflict");
+    };
+
+    assert_eq!(version(&sandbox), 1);
+
+    sandbox.ok(&[
+        "edit",
+        "update",
+        "shoes",
+        "a",
+        r#"{"price": 25}"#,
+        "-v",
+        "1",
+    ]);
+    assert_eq!(version(&sandbox), 2);
+    conflict(&["update", "shoes", "a", r#"{"price": 20}"#, "-v", "1"]);
+
+    sandbox.ok(&[
+        "edit",
+        "patch",
+        "shoes",
+        "a",
+        r#"{"sale": true}"#,
+        "-v",
+        "2",
+    ]);
+    assert_eq!(version(&sandbox), 3);
+    conflict(&["patch", "shoes", "a", r#"{"sale": false}"#, "-v", "2"]);
+
+    conflict(&["remove", "shoes", "a", "-v", "2"]);
+    assert_eq!(version(&sandbox), 3);
+
+    let output = sandbox.ok(&["--output", "json", "edit", "get", "shoes", "a"]);
+    let report: Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(report["data"]["data"], json!({"price": 25, "sale": true}));
+
+    // text output exits with the same code
+    let output = sandbox.run(&["edit", "remove", "shoes", "a", "-v", "2"]);
+    assert_eq!(output.status.code(), Some(3));
+
+    sandbox.ok(&["edit", "remove", "shoes", "a", "-v", "3"]);
+    assert!(!sandbox.run(&["edit", "get", "shoes", "a"]).status.success());
+}
+
+#[test]
+fn mget_lists_missing_keys_and_keeps_only_the_fields_asked_for() {
+    let sandbox = Sandbox::new("mget_fields");
+    sandbox.ok(&["manage", "create", "shoes"]);
+    sandbox.ok(&[
+        "edit",
+        "add",
+        "shoes",
+        "a",
+        r#"{"title": "red shoes", "price": 10, "brand": {"name": "nike", "country": "us"}}"#,
+    ]);
+    sandbox.ok(&[
+        "edit",
+        "add",
+        "shoes",
+        "b",
+        r#"{"title": "blue shoes", "price": 20}"#,
+    ]);
+
+    let output = sandbox.ok(&[
+        "--output",
+        "json",
+        "edit",
+        "mget",
+        "shoes",
+        "a",
+        "nope",
+        "b",
+        "--fields",
+        "title,brand.name",
+    ]);
+    let report: Value = serde_json::from_str(&output).unwrap();
+
+    assert_eq!(report["status"], "warning");
+    assert_eq!(report["count"], 2);
+    assert_eq!(
+        report["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| (&entry["key"], &entry["data"]))
+            .collect::<Vec<_>>(),
+        [
+            (
+                &json!("a"),
+                &json!({"title": "red shoes", "brand": {"name": "nike"}})
+            ),
+            (&json!("nope"), &json!(null)),
+            (&json!("b"), &json!({"title": "blue shoes"})),
+        ]
+    );
+    assert_eq!(report["data"][0]["version"], 1);
+
+    let output = sandbox.ok(&[
+        "--output",
+        "json",
+        "edit",
+        "get",
+        "shoes",
+        "a",
+        "-f",
+        "brand.name,price",
+    ]);
+    let report: Value = serde_json::from_str(&output).unwrap();
+
+    assert_eq!(
+        report["data"]["data"],
+        json!({"brand": {"name": "nike"}, "price": 10})
+    );
+
+    let output = sandbox.ok(&["--output", "json", "edit", "mget", "shoes", "a", "b"]);
+    let report: Value = serde_json::from_str(&output).unwrap();
+
+    assert_eq!(report["status"], "success");
+    assert_eq!(report["data"][0]["data"]["brand"]["country"], "us");
+}
+
+#[test]
+fn patches_return_the_key_warning() {
+    let engine = Engine::open_in_memory().unwrap();
+    let index = engine.create_index("shoes").unwrap();
+    index.set_key_field("sku").unwrap();
+
+    index.add_document("a", &json!({"sku": "a"})).unwrap();
+
+    let (hit, warning) = index
+        .patch_document("a", &json!({"title": "red"}), PatchFormat::Merge, None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(hit.version, 2);
+    assert_eq!(warning, None);
+
+    let (hit, warning) = index
+        .patch_document("a", &json!({"sku": "b"}), PatchFormat::Merge, None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(hit.document["sku"], "b");
+    assert_eq!(warning.as_deref(), Some("its `sku` is 'b' instead"));
+
+    index.set_key_check(KeyCheck::Reject).unwrap();
+
+    assert!(matches!(
+        index.patch_document("a", &json!({"sku": "c"}), PatchFormat::Merge, None),
+        Err(Error::KeyMismatch { .. })
+    ));
+}
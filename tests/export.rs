@@ -0,0 +1,91 @@
+mod common;
+
+use common::Sandbox;
+use serde_json::Value;
+
+const SCHEMA: &str = r#"{"price": "integer", "rating": "float", "stock": "bool"}"#;
+
+fn shoes(sandbox: &Sandbox) {
+    sandbox.ok(&["manage", "create", "shoes", "-s", SCHEMA]);
+
+    for (key, document) in [
+        (
+            "a",
+            r#"{"sku": "a", "title": "red shoes", "price": 30, "rating": 4.5, "stock": true}"#,
+        ),
+        (
+            "b",
+            r#"{"sku": "b", "title": "blue, \"suede\" shoes", "price": 80, "rating": 3.5, "stock": false}"#,
+        ),
+        (
+            "c",
+            r#"{"sku": "c", "title": "red boots", "price": 120, "rating": 5.0, "stock": true}"#,
+        ),
+    ] {
+        sandbox.ok(&["edit", "add", "shoes", key, document]);
+    }
+}
+
+// every document of the index, in the order they're exported
+fn documents(sandbox: &Sandbox, index: &str, query: Option<&str>) -> Vec<Value> {
+    let mut args = vec!["manage", "export", index, "-f", "ndjson"];
+    if let Some(query) = query {
+        args.extend(["-q", query]);
+    }
+
+    sandbox
+        .ok(&args)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn exports_import_back_as_the_same_documents() {
+    let sandbox = Sandbox::new("export_round_trip");
+    shoes(&sandbox);
+
+    let original = documents(&sandbox, "shoes", None);
+    assert_eq!(original.len(), 3);
+    assert_eq!(original[1]["title"], "blue, \"suede\" shoes");
+
+    for format in ["csv", "ndjson", "json"] {
+        let path = sandbox.dir.join(format!("shoes.{}", format));
+        let path = path.to_str().unwrap();
+        let copy = format!("copy{}", format);
+
+        let output = sandbox.ok(&["--output", "json", "manage", "export", "shoes", "-t", path]);
+        let report: Value = serde_json::from_str(&output).unwrap();
+
+        assert_eq!(report["count"], 3);
+        assert_eq!(report["data"]["format"], format);
+
+        sandbox.ok(&["manage", "create", &copy, "-s", SCHEMA]);
+        sandbox.ok(&["manage", "import", &copy, "sku", path]);
+
+        assert_eq!(documents(&sandbox, &copy, None), original, "{}", format);
+    }
+}
+
+#[test]
+fn exports_only_write_the_documents_the_query_finds() {
+    let sandbox = Sandbox::new("export_query");
+    shoes(&sandbox);
+
+    let keys = |query: &str| -> Vec<Value> {
+        documents(&sandbox, "shoes", Some(query))
+            .iter()
+            .map(|document| document["sku"].clone())
+            .collect()
+    };
+
+    assert_eq!(keys("red"), ["a", "c"]);
+    assert_eq!(keys("red boots"), ["c"]);
+    assert!(keys("green").is_empty());
+
+    let csv = sandbox.ok(&["manage", "export", "shoes", "-f", "csv", "-q", "blue"]);
+    assert_eq!(
+        csv,
+        "price,rating,sku,stock,title\n80,3.5,b,false,\"blue, \"\"suede\"\" shoes\"\n"
+    );
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::Sandbox;
+
+// a command, one of its actions and the usage shown for it
+const ACTIONS: [(&str, &str, &str); 7] = [
+    ("manage", "info", "manage info {index}"),
+    (
+        "edit",
+        "get",
+        "edit get {index} {key} {?fields|-f} {?--compact|-c}",
+    ),
+    (
+        "search",
+        "suggest",
+        "search suggest {index} {query...} {?filter|-f} {?count|-n} {?limit|-l}",
+    ),
+    ("rank", "weight", "rank weight {index} {field} {weight}"),
+    ("config", "set", "config set {setting} {value}"),
+    ("serve", "start", "serve start {?address|-a}"),
+    ("completions", "bash", "completions bash"),
+];
+
+#[test]
+fn every_command_lists_its_actions() {
+    let sandbox = Sandbox::new("help_commands");
+
+    let help = sandbox.ok(&["help"]);
+
+    for (command, action, _) in ACTIONS {
+        assert!(help.contains(&format!("{}:", command)), "{}", help);
+
+        // unknown actions fall back to the help of the command
+        for args in [vec![command], vec![command, "help"], vec![command, "nope"]] {
+            let output = sandbox.ok(&args);
+
+            assert!(output.contains(" Command ##"), "{:?}: {}", args, output);
+            assert!(
+                output.contains(&format!("      {}:", action)),
+                "{:?}: {}",
+                args,
+                output
+            );
+        }
+    }
+}
+
+#[test]
+fn every_action_shows_its_usage() {
+    let sandbox = Sandbox::new("help_actions");
+
+    for (command, action, usage) in ACTIONS {
+        for flag in ["--help", "-h"] {
+            let output = sandbox.ok(&[command, action, flag]);
+
+            assert!(output.contains(&format!("## {} {} ##", command, action)));
+            assert!(output.contains(usage), "{}: {}", usage, output);
+        }
+    }
+}
+
+#[test]
+fn param_errors_end_with_the_usage() {
+    let sandbox = Sandbox::new("help_usage");
+
+    for (command, action, usage) in ACTIONS {
+        if !usage.contains("{index}") && !usage.contains("{setting}") {
+            continue;
+        }
+
+        let output = sandbox.run(&[command, action]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert!(!output.status.success());
+        assert!(
+            stderr.contains(&format!("usage: {}", usage)),
+            "{}: {}",
+            usage,
+            stderr
+        );
+    }
+
+    let output = sandbox.run(&["manage", "info", "shoes", "--nope"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("Unknown param: --nope, usage: manage info {index}"));
+}
@@ -0,0 +1,190 @@
+mod common;
+
+use common::Sandbox;
+use serde_json::Value;
+use std::fs;
+
+#[test]
+fn csv_cells_are_read_as_their_schema_type() {
+    let sandbox = Sandbox::new("csv_types");
+    let csv = sandbox.write(
+        "products.csv",
+        "sku,name,price,rating,stock\na,red shoes,30,4.5,true\nb,blue shoes,80,3,false\n",
+    );
+
+    sandbox.ok(&[
+        "manage",
+        "create",
+        "typed",
+        "-s",
+        r#"{"price": "integer", "rating": "float", "stock": "bool"}"#,
+    ]);
+    sandbox.ok(&["manage", "import", "typed", "sku", &csv]);
+
+    let output = sandbox.ok(&["--output", "json", "edit", "get", "typed", "a"]);
+    let report: Value = serde_json::from_str(&output).unwrap();
+
+    assert_eq!(report["data"]["data"]["price"], 30);
+    assert_eq!(report["data"]["data"]["rating"], 4.5);
+    assert_eq!(report["data"]["data"]["stock"], true);
+    assert_eq!(report["data"]["data"]["name"], "red shoes");
+}
+
+#[test]
+fn csv_cells_that_dont_parse_fail_their_row() {
+    let sandbox = Sandbox::new("csv_bad_cell");
+    let csv = sandbox.write("products.csv", "sku,price\na,30\nb,cheap\n");
+
+    sandbox.ok(&["manage", "create", "typed", "-s", r#"{"price": "integer"}"#]);
+
+    let output = sandbox.run(&["--output", "json", "manage", "import", "typed", "sku", &csv]);
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(report["status"], "warning");
+    assert_eq!(report["data"]["inserted"], 1);
+    assert_eq!(report["data"]["failed"], 1);
+    assert_eq!(report["data"]["failures"][0]["record"], 2);
+    assert!(report["data"]["failures"][0]["error"]
+        .as_str()
+        .unwrap()
+        .ends_with("price: expected integer, got \"cheap\""));
+}
+
+#[test]
+fn imports_where_every_record_fails_exit_with_an_error() {
+    let sandbox = Sandbox::new("all_failed");
+    let csv = sandbox.write(
+        "products.csv",
+        "sku,price
+a,cheap
+b,free
+",
+    );
+
+    sandbox.ok(&["manage", "create", "typed", "-s", r#"{"price": "integer"}"#]);
+
+    let output = sandbox.run(&["--output", "json", "manage", "import", "typed", "sku", &csv]);
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert!(!output.status.success());
+    assert_eq!(report["status"], "error");
+    assert_eq!(report["data"]["failed"], 2);
+    assert_eq!(
+        output.stdout.iter().filter(|byte| **byte == b'\n').count(),
+        1
+    );
+
+    let output = sandbox.run(&["manage", "import", "typed", "sku", &csv]);
+
+    assert!(!output.status.success());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn key_fields_can_have_underscores() {
+    let sandbox = Sandbox::new("underscore_key");
+    let csv = sandbox.write("products.csv", "product_id,name\np1,red shoes\n");
+
+    sandbox.ok(&["manage", "import", "typed", "product_id", &csv]);
+    sandbox.ok(&["manage", "init", "other", "sku_id", r#"[{"sku_id": "s1"}]"#]);
+    sandbox.ok(&["manage", "create", "empty", "-k", "sku_id"]);
+    sandbox.ok(&["edit", "get", "typed", "p1"]);
+    sandbox.ok(&["edit", "get", "other", "s1"]);
+}
+
+#[test]
+fn dotted_key_fields_are_read_from_nested_objects() {
+    let sandbox = Sandbox::new("dotted_key");
+
+    sandbox.ok(&[
+        "manage",
+        "init",
+        "typed",
+        "brand.id",
+        r#"[{"brand": {"id": "nike"}}]"#,
+    ]);
+    sandbox.ok(&["manage", "key", "typed", "brand.id", "reject"]);
+    sandbox.ok(&[
+        "edit",
+        "add",
+        "typed",
+        "-",
+        r#"{"brand": {"id": "adidas"}}"#,
+    ]);
+    sandbox.ok(&["edit", "get", "typed", "nike"]);
+    sandbox.ok(&["edit", "get", "typed", "adidas"]);
+
+    let output = sandbox.run(&[
+        "edit",
+        "add",
+        "typed",
+        "puma",
+        r#"{"brand": {"id": "asics"}}"#,
+    ]);
+    assert!(!output.status.success());
+}
+
+// the number of entries in the index, or None when there is no index
+fn documents(sandbox: &Sandbox, index: &str) -> Option<u64> {
+    let output = sandbox.run(&["--output", "json", "manage", "info", index]);
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    report["data"]["documents"].as_u64()
+}
+
+const RECORDS: &str = "{\"sku\": \"a\"}\n{\"sku\": \"b\"\n{\"sku\": \"c\"}\n";
+
+#[test]
+fn atomic_imports_keep_nothing_when_a_record_fails() {
+    let sandbox = Sandbox::new("atomic_import");
+    let ndjson = sandbox.write("products.ndjson", RECORDS);
+
+    let output = sandbox.run(&["manage", "import", "fresh", "sku", &ndjson, "-m", "atomic"]);
+    assert!(!output.status.success());
+    assert_eq!(documents(&sandbox, "fresh"), None);
+
+    sandbox.ok(&["manage", "init", "shoes", "sku", r#"[{"sku": "x"}]"#]);
+
+    let output = sandbox.run(&["manage", "import", "shoes", "sku", &ndjson, "-m", "atomic"]);
+    assert!(!output.status.success());
+    assert_eq!(documents(&sandbox, "shoes"), Some(1));
+}
+
+#[test]
+fn continue_imports_keep_every_good_record() {
+    let sandbox = Sandbox::new("continue_import");
+    let ndjson = sandbox.write("products.ndjson", RECORDS);
+
+    let output = sandbox.ok(&[
+        "--output", "json", "manage", "import", "shoes", "sku", &ndjson, "-m", "continue",
+    ]);
+    let report: Value = serde_json::from_str(&output).unwrap();
+
+    assert_eq!(report["status"], "warning");
+    assert_eq!(report["data"]["inserted"], 2);
+    assert_eq!(report["data"]["failures"][0]["record"], 2);
+    assert_eq!(documents(&sandbox, "shoes"), Some(2));
+}
+
+#[test]
+fn batches_committed_before_a_failed_read_are_kept() {
+    let sandbox = Sandbox::new("committed_batches");
+    let path = sandbox.dir.join("products.ndjson");
+    let ndjson = path.to_string_lossy().to_string();
+
+    // the last line isn't utf-8, so reading stops there
+    fs::write(&path, b"{\"sku\": \"a\"}\n{\"sku\": \"b\"}\n\xff\xfe\n").unwrap();
+
+    let output = sandbox.run(&[
+        "manage", "import", "kept", "sku", &ndjson, "-m", "continue", "-b", "1",
+    ]);
+    assert!(!output.status.success());
+    assert_eq!(documents(&sandbox, "kept"), Some(2));
+
+    let output = sandbox.run(&[
+        "manage", "import", "dropped", "sku", &ndjson, "-m", "continue", "-b", "10",
+    ]);
+    assert!(!output.status.success());
+    assert_eq!(documents(&sandbox, "dropped"), None);
+}
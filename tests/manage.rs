@@ -0,0 +1,93 @@
+mod common;
+
+use common::Sandbox;
+use serde_json::{json, Value};
+
+#[test]
+fn deleting_a_missing_index_fails() {
+    let sandbox = Sandbox::new("delete_missing");
+
+    let output = sandbox.run(&["--output", "json", "manage", "delete", "nope"]);
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert!(!output.status.success());
+    assert_eq!(report["status"], "error");
+    assert_eq!(report["error"], "No index called 'nope'");
+
+    sandbox.ok(&["manage", "create", "shoes"]);
+    sandbox.ok(&["manage", "delete", "shoes"]);
+    assert!(!sandbox.run(&["manage", "delete", "shoes"]).status.success());
+}
+
+// the report data of a json run
+fn data(sandbox: &Sandbox, args: &[&str]) -> Value {
+    let mut json = vec!["--output", "json"];
+    json.extend(args);
+
+    let report: Value = serde_json::from_str(&sandbox.ok(&json)).unwrap();
+
+    report["data"].clone()
+}
+
+#[test]
+fn list_and_info_follow_adds_and_removes() {
+    let sandbox = Sandbox::new("list_info");
+
+    assert_eq!(data(&sandbox, &["manage", "list"]), json!([]));
+
+    sandbox.ok(&[
+        "manage",
+        "init",
+        "shoes",
+        "sku",
+        r#"[{"sku": "a", "name": "red shoes"}, {"sku": "b", "name": "blue shoes"}, {"sku": "c", "name": "hat"}]"#,
+    ]);
+    sandbox.ok(&["manage", "create", "hats", "-s", r#"{"price": "integer"}"#]);
+
+    let list = data(&sandbox, &["manage", "list"]);
+    let names: Vec<&Value> = list
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|info| &info["name"])
+        .collect();
+
+    assert_eq!(names, ["hats", "shoes"]);
+    assert_eq!(list[0]["documents"], 0);
+    assert_eq!(list[0]["key_field"], Value::Null);
+    assert_eq!(list[1]["documents"], 3);
+    assert_eq!(list[1]["key_field"], "sku");
+    assert_eq!(list[1]["key_check"], "warn");
+    assert!(list[1]["size"].as_u64().unwrap() > 0);
+    assert!(list[1]["created"].is_string());
+    assert!(list[1]["updated"].as_str() >= list[1]["created"].as_str());
+
+    sandbox.ok(&[
+        "edit",
+        "add",
+        "shoes",
+        "d",
+        r#"{"sku": "d", "name": "green shoes"}"#,
+    ]);
+    assert_eq!(data(&sandbox, &["manage", "info", "shoes"])["documents"], 4);
+
+    sandbox.ok(&["edit", "remove", "shoes", "a"]);
+    sandbox.ok(&["edit", "remove-by-query", "shoes", "hat", "--apply"]);
+    assert_eq!(data(&sandbox, &["manage", "info", "shoes"])["documents"], 2);
+
+    sandbox.ok(&["manage", "key", "shoes", "sku", "reject"]);
+    sandbox.ok(&["rank", "weight", "shoes", "name", "2"]);
+
+    let info = data(&sandbox, &["manage", "info", "shoes"]);
+    assert_eq!(info["key_check"], "reject");
+    assert_eq!(info["rank"]["weights"][0]["field"], "name");
+
+    let info = data(&sandbox, &["manage", "info", "hats"]);
+    assert_eq!(info["schema"]["price"]["type"], "integer");
+
+    sandbox.ok(&["manage", "delete", "hats"]);
+
+    let list = data(&sandbox, &["manage", "list"]);
+    assert_eq!(list.as_array().unwrap().len(), 1);
+    assert_eq!(list[0]["documents"], 2);
+}
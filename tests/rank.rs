@@ -0,0 +1,82 @@
+use rusty_search::{Direction, Engine, Error, SearchOptions};
+use serde_json::json;
+
+#[test]
+fn weights_must_be_above_zero_and_boosts_finite() {
+    let engine = Engine::open_in_memory().unwrap();
+    let index = engine.create_index("shoes").unwrap();
+
+    for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        assert!(matches!(
+            index.set_weight("title", value),
+            Err(Error::InvalidRank(_))
+        ));
+        assert!(matches!(
+            index.set_boost("rating", value),
+            Err(Error::InvalidRank(_))
+        ));
+    }
+
+    for value in [0.0, -1.0] {
+        assert!(matches!(
+            index.set_weight("title", value),
+            Err(Error::InvalidRank(_))
+        ));
+    }
+
+    index.set_weight("title", 2.5).unwrap();
+    index.set_boost("rating", -0.5).unwrap();
+
+    let settings = index.rank_settings().unwrap();
+
+    assert_eq!(settings.weights.len(), 1);
+    assert_eq!(settings.boosts.len(), 1);
+}
+
+#[test]
+fn rank_rules_change_the_order_of_hits() {
+    let engine = Engine::open_in_memory().unwrap();
+    let index = engine.create_index("shoes").unwrap();
+
+    index
+        .add_document(
+            "a",
+            &json!({ "title": "red shoes", "body": "plain", "rating": 1 }),
+        )
+        .unwrap();
+    index
+        .add_document(
+            "b",
+            &json!({ "title": "plain", "body": "red shoes", "rating": 5 }),
+        )
+        .unwrap();
+    index
+        .add_document(
+            "c",
+            &json!({ "title": "red shoes", "body": "plain", "rating": 3 }),
+        )
+        .unwrap();
+
+    let order = || index.search_keys("red", &SearchOptions::default()).unwrap();
+
+    index.set_weight("title", 10.0).unwrap();
+    assert_eq!(order()[2], "b");
+
+    index.reset_rank(None).unwrap();
+    index.set_weight("body", 10.0).unwrap();
+    assert_eq!(order()[0], "b");
+
+    // a and c match the same way, so only the sort tells them apart
+    index.set_sort("rating", Direction::Desc).unwrap();
+    assert_eq!(order(), ["b", "c", "a"]);
+
+    index.set_sort("rating", Direction::Asc).unwrap();
+    assert_eq!(order(), ["b", "a", "c"]);
+
+    index.reset_rank(None).unwrap();
+    index.set_boost("rating", 100.0).unwrap();
+    assert_eq!(order(), ["b", "c", "a"]);
+
+    index.set_boost("rating", -100.0).unwrap();
+    assert_eq!(order(), ["a", "c", "b"]);
+}
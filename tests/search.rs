@@ -0,0 +1,316 @@
+mod common;
+
+use common::Sandbox;
+use rusty_search::{Direction, Engine, Index, SearchOptions};
+use serde_json::{json, Value};
+use std::{env, fs, process};
+
+fn shoes(name: &str) -> Sandbox {
+    let sandbox = Sandbox::new(name);
+
+    sandbox.ok(&[
+        "manage",
+        "init",
+        "shoes",
+        "sku",
+        r#"[
+            {"sku": "a", "name": "red shoes", "brand": "nike", "price": 30},
+            {"sku": "b", "name": "blue shoes", "brand": "adidas", "price": 80},
+            {"sku": "c", "name": "green shoes", "brand": "nike", "price": 120},
+            {"sku": "d", "name": "hat", "brand": "puma", "price": 15}
+        ]"#,
+    ]);
+
+    sandbox
+}
+
+// the keys a query finds, in order
+fn found(index: &Index, query: &str) -> Vec<String> {
+    index
+        .search(query, &SearchOptions::default())
+        .unwrap()
+        .hits
+        .into_iter()
+        .map(|hit| hit.key)
+        .collect()
+}
+
+#[test]
+fn ndjson_prints_a_line_per_hit_then_the_report() {
+    let sandbox = shoes("ndjson_hits");
+    let output = sandbox.ok(&[
+        "--output", "ndjson", "search", "query", "shoes", "shoes", "--facets", "brand",
+    ]);
+
+    let lines: Vec<Value> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert_eq!(lines.len(), 4);
+    for hit in &lines[..3] {
+        assert!(hit["key"].is_string());
+        assert!(hit["data"]["name"].is_string());
+    }
+
+    let report = &lines[3];
+    assert_eq!(report["status"], "success");
+    assert_eq!(report["count"], 3);
+    assert_eq!(report["summary"]["total"], 3);
+    assert_eq!(report["summary"]["facets"]["brand"]["nike"], 2);
+}
+
+#[test]
+fn json_has_the_same_shape_with_or_without_facets() {
+    let sandbox = shoes("json_shape");
+
+    for facets in [&["--facets", "brand"][..], &[]] {
+        let mut args = vec![
+            "--output", "json", "search", "query", "shoes", "shoes", "-l", "2",
+        ];
+        args.extend(facets);
+
+        let report: Value = serde_json::from_str(&sandbox.ok(&args)).unwrap();
+
+        assert_eq!(report["data"].as_array().unwrap().len(), 2);
+        assert_eq!(report["summary"]["total"], 3);
+        assert!(report["summary"]["next"].is_string());
+        assert_eq!(report["summary"]["facets"].is_null(), facets.is_empty());
+    }
+}
+
+#[test]
+fn facets_count_prefix_matches() {
+    let sandbox = shoes("prefix_facets");
+    let output = sandbox.ok(&[
+        "--output",
+        "json",
+        "search",
+        "query",
+        "shoes",
+        "sho",
+        "--prefix",
+        "--facets",
+        "brand,price",
+    ]);
+    let report: Value = serde_json::from_str(&output).unwrap();
+
+    assert_eq!(report["data"].as_array().unwrap().len(), 3);
+    assert_eq!(report["summary"]["facets"]["brand"]["nike"], 2);
+    assert_eq!(report["summary"]["facets"]["brand"]["adidas"], 1);
+    assert!(!report["summary"]["facets"]["price"]
+        .as_array()
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn search_follows_adds_updates_and_removes() {
+    let engine = Engine::open_in_memory().unwrap();
+    let index = engine.create_index("shoes").unwrap();
+
+    index
+        .add_document("a", &json!({"name": "red shoes"}))
+        .unwrap();
+    index
+        .add_document("b", &json!({"name": "blue shoes"}))
+        .unwrap();
+    assert_eq!(found(&index, "shoes"), ["a", "b"]);
+    assert_eq!(found(&index, "red"), ["a"]);
+
+    index
+        .update_document("a", &json!({"name": "green boots"}), None)
+        .unwrap();
+    assert_eq!(found(&index, "shoes"), ["b"]);
+    assert!(found(&index, "red").is_empty());
+    assert_eq!(found(&index, "green"), ["a"]);
+
+    index.remove_document("b", None).unwrap();
+    assert!(found(&index, "shoes").is_empty());
+    assert_eq!(found(&index, "boots"), ["a"]);
+
+    // a key added again after its removal only finds what it has now
+    index.add_document("b", &json!({"name": "hat"})).unwrap();
+    assert!(found(&index, "blue").is_empty());
+    assert_eq!(found(&index, "hat"), ["b"]);
+    assert_eq!(index.sync().unwrap(), 2);
+    assert_eq!(found(&index, "hat"), ["b"]);
+}
+
+#[test]
+fn search_tables_numbered_the_old_way_are_rebuilt() {
+    let path = env::temp_dir().join(format!("rusty_search_old_rows_{}.db", process::id()));
+    let _ = fs::remove_file(&path);
+
+    {
+        let engine = Engine::open(&path).unwrap();
+        let index = engine.create_index("shoes").unwrap();
+
+        index
+            .add_document("a", &json!({"name": "red shoes"}))
+            .unwrap();
+    }
+
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .execute_batch(
+            "DELETE FROM `shoes_search`;
+            INSERT INTO `shoes_search` (`rowid`, `key`, `field`, `value`)
+            VALUES (1, 'a', 'name', 'red shoes');",
+        )
+        .unwrap();
+
+    let engine = Engine::open(&path).unwrap();
+    let index = engine.index("shoes").unwrap();
+
+    index
+        .update_document("a", &json!({"name": "blue shoes"}), None)
+        .unwrap();
+    assert!(found(&index, "red").is_empty());
+    assert_eq!(found(&index, "blue"), ["a"]);
+
+    drop(engine);
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn pages_after_next_return_every_hit_once_even_with_ties() {
+    let engine = Engine::open_in_memory().unwrap();
+    let index = engine.create_index("shoes").unwrap();
+
+    // the same words and only three prices, so most hits tie on everything
+    for i in 0..25 {
+        index
+            .add_document(
+                &format!("{:02}", (i * 7) % 25),
+                &json!({ "name": "red shoes", "price": i % 3 }),
+            )
+            .unwrap();
+    }
+
+    for (query, sort) in [
+        ("shoes", vec![]),
+        ("red shoes", vec![("price".to_string(), Direction::Desc)]),
+        ("red", vec![("price".to_string(), Direction::Asc)]),
+    ] {
+        let options = SearchOptions {
+            sort,
+            ..Default::default()
+        };
+        let every = index.search(query, &options).unwrap();
+        assert_eq!(every.total, 25);
+        assert_eq!(every.next, None);
+
+        let mut paged = Vec::new();
+        let mut after = None;
+
+        loop {
+            let page = index
+                .search(
+                    query,
+                    &SearchOptions {
+                        limit: Some(4),
+                        after: after.take(),
+                        ..options.clone()
+                    },
+                )
+                .unwrap();
+
+            assert_eq!(page.total, 25);
+            paged.extend(page.hits.into_iter().map(|hit| hit.key));
+
+            match page.next {
+                Some(next) => after = Some(next),
+                None => break,
+            }
+        }
+
+        let keys: Vec<String> = every.hits.into_iter().map(|hit| hit.key).collect();
+        assert_eq!(paged, keys, "{:?}", query);
+
+        paged.sort();
+        paged.dedup();
+        assert_eq!(paged.len(), 25);
+    }
+}
+
+#[test]
+fn exact_matches_rank_above_fuzzy_ones_and_the_metric_counts_edits() {
+    let sandbox = Sandbox::new("fuzzy_search");
+    sandbox.ok(&[
+        "manage",
+        "init",
+        "shoes",
+        "sku",
+        r#"[
+            {"sku": "a", "name": "sheo"},
+            {"sku": "b", "name": "a shoe for running on long and rocky mountain trails"},
+            {"sku": "c", "name": "shoes"}
+        ]"#,
+    ]);
+
+    let keys = |args: &[&str]| -> Vec<Value> {
+        let mut search = vec!["--output", "json", "search", "query", "shoes", "shoe"];
+        search.extend(args);
+
+        let report: Value = serde_json::from_str(&sandbox.ok(&search)).unwrap();
+
+        report["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|hit| hit["key"].clone())
+            .collect()
+    };
+
+    assert_eq!(keys(&[]), ["b"]);
+
+    // `sheo` is one swap from `shoe`, but a delete and an insert apart
+    let damerau = keys(&["-z", "1"]);
+    assert_eq!(damerau[0], "b");
+    assert_eq!(damerau.len(), 3);
+
+    assert_eq!(keys(&["-z", "1", "--metric", "levenshtein"]), ["b", "c"]);
+    assert_eq!(keys(&["-z", "1", "--metric", "damerau"]), damerau);
+}
+
+#[test]
+fn suggestions_come_most_used_word_first_and_only_when_they_find_entries() {
+    let engine = Engine::open_in_memory().unwrap();
+    let index = engine.create_index("shoes").unwrap();
+
+    for (key, name) in [
+        ("a", "red shoes"),
+        ("b", "red shoes"),
+        ("c", "blue shorts"),
+        ("d", "red shirt"),
+        ("e", "blue shoes"),
+    ] {
+        index.add_document(key, &json!({ "name": name })).unwrap();
+    }
+
+    let suggested = |query: &str, count: usize| -> Vec<(String, usize)> {
+        index
+            .suggest(query, &SearchOptions::default(), count)
+            .unwrap()
+            .into_iter()
+            .map(|suggestion| (suggestion.query, suggestion.total))
+            .collect()
+    };
+
+    // `shorts` is in the dictionary but never next to `red`
+    assert_eq!(
+        suggested("red sh", 5),
+        [("red shoes".to_string(), 2), ("red shirt".to_string(), 1)]
+    );
+    assert_eq!(suggested("red sh", 1), [("red shoes".to_string(), 2)]);
+    assert_eq!(
+        suggested("sh", 5),
+        [
+            ("shoes".to_string(), 3),
+            ("shirt".to_string(), 1),
+            ("shorts".to_string(), 1)
+        ]
+    );
+    assert!(suggested("red x", 5).is_empty());
+}
@@ -0,0 +1,170 @@
+mod common;
+
+use common::Sandbox;
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Stdio};
+use std::thread;
+use std::time::Duration;
+
+// the api served from a sandbox on a free local port, stopped when dropped
+struct Server {
+    child: Child,
+    address: String,
+}
+
+impl Server {
+    fn start(sandbox: &Sandbox) -> Self {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let address = format!("127.0.0.1:{}", port);
+        let server = Self {
+            child: sandbox
+                .command()
+                .args(["serve", "start", "--address", &address])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .unwrap(),
+            address,
+        };
+
+        for _ in 0..100 {
+            if TcpStream::connect(&server.address).is_ok() {
+                return server;
+            }
+
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        panic!("the server didn't start on {}", server.address);
+    }
+
+    // the status and json body of the reply
+    fn request(&self, method: &str, path: &str, body: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(&self.address).unwrap();
+
+        write!(
+            stream,
+            "{} {} HTTP/1.0\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply).unwrap();
+
+        let (head, body) = reply.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+
+        (status, serde_json::from_str(body).unwrap())
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn plus_signs_in_paths_stay_plus_signs() {
+    let sandbox = Sandbox::new("serve_plus");
+    sandbox.ok(&["manage", "create", "langs"]);
+
+    let server = Server::start(&sandbox);
+
+    let (status, _) = server.request(
+        "POST",
+        "/indexes/langs/documents/c%2B%2B",
+        r#"{"name": "c++"}"#,
+    );
+    assert_eq!(status, 201);
+
+    let (status, body) = server.request("GET", "/indexes/langs/documents/c++", "");
+    assert_eq!(status, 200);
+    assert_eq!(body["key"], "c++");
+
+    let (status, _) = server.request("GET", "/indexes/langs/documents/c%20%20", "");
+    assert_eq!(status, 404);
+}
+
+#[test]
+fn plus_signs_in_query_strings_are_spaces() {
+    let sandbox = Sandbox::new("serve_query_plus");
+    sandbox.ok(&[
+        "manage",
+        "init",
+        "shoes",
+        "sku",
+        r#"[{"sku": "a", "title": "red shoes"}]"#,
+    ]);
+
+    let server = Server::start(&sandbox);
+
+    let (status, body) = server.request(
+        "GET",
+        "/indexes/shoes/search?filter=title+%3D+%22red+shoes%22",
+        "",
+    );
+    assert_eq!(status, 200);
+    assert_eq!(body["total"], 1);
+}
+
+#[test]
+fn signs_after_a_percent_arent_hex_digits() {
+    let sandbox = Sandbox::new("serve_percent_sign");
+    sandbox.ok(&["manage", "create", "langs"]);
+
+    let server = Server::start(&sandbox);
+
+    let (status, _) = server.request("POST", "/indexes/langs/documents/a%25%2B5", "{}");
+    assert_eq!(status, 201);
+
+    let (status, body) = server.request("GET", "/indexes/langs/documents/a%+5", "");
+    assert_eq!(status, 200);
+    assert_eq!(body["key"], "a%+5");
+}
+
+#[test]
+fn bodies_over_the_limit_are_too_large() {
+    let sandbox = Sandbox::new("serve_too_large");
+    sandbox.ok(&["manage", "create", "langs"]);
+
+    let server = Server::start(&sandbox);
+
+    // one byte over the 16 MiB limit
+    let body = "x".repeat(16 * 1024 * 1024 + 1);
+
+    let (status, reply) = server.request("POST", "/indexes/langs/documents/a", &body);
+    assert_eq!(status, 413);
+    assert_eq!(reply["status"], "error");
+
+    let (status, _) = server.request("POST", "/indexes/langs/documents/a", "{}");
+    assert_eq!(status, 201);
+}
+
+#[test]
+fn database_failures_are_server_errors() {
+    let sandbox = Sandbox::new("serve_database_failure");
+    sandbox.ok(&["manage", "create", "langs"]);
+
+    let server = Server::start(&sandbox);
+
+    let (status, _) = server.request("POST", "/indexes/langs/documents/a", "not json");
+    assert_eq!(status, 400);
+
+    sandbox.write("test.db", "not a database");
+
+    let (status, body) = server.request("GET", "/indexes", "");
+    assert_eq!(status, 500);
+    assert_eq!(body["status"], "error");
+}